@@ -0,0 +1,89 @@
+//! Benchmarks the ARM and THUMB decoders over a stream of opcodes.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo bench -p arm7tdmi --bench decode
+//! ```
+//!
+//! Under `cargo test`, each benchmark is run once as a smoke test.
+//!
+//! The THUMB decoder is also measured against a lookup table of every decoded
+//! opcode, giving a baseline for a table-driven decoder.
+
+use std::hint::black_box;
+
+use arm7tdmi::{arm, thumb};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// Number of opcodes in each stream.
+const STREAM_LEN: usize = 4096;
+
+/// Returns a deterministic stream of pseudo-random words.
+fn stream() -> impl Iterator<Item = u32> {
+    // xorshift32
+    let mut state = 0x2545_F491_u32;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    })
+    .take(STREAM_LEN)
+}
+
+/// ARM opcodes, mostly with the `AL` condition as in typical code.
+fn arm_opcodes() -> Vec<u32> {
+    stream()
+        .enumerate()
+        .map(|(i, word)| match i % 8 {
+            0 => word,
+            _ => 0xE000_0000 | (word & 0x0FFF_FFFF),
+        })
+        .collect()
+}
+
+fn thumb_opcodes() -> Vec<u16> {
+    stream().map(|word| word as u16).collect()
+}
+
+fn arm_decode(c: &mut Criterion) {
+    let opcodes = arm_opcodes();
+
+    let mut group = c.benchmark_group("arm");
+    group.throughput(Throughput::Elements(opcodes.len() as u64));
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            for &opcode in &opcodes {
+                black_box(arm::decode(black_box(opcode)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn thumb_decode(c: &mut Criterion) {
+    let opcodes = thumb_opcodes();
+    let table: Vec<thumb::Instruction> = (0..=u16::MAX).map(thumb::decode).collect();
+
+    let mut group = c.benchmark_group("thumb");
+    group.throughput(Throughput::Elements(opcodes.len() as u64));
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            for &opcode in &opcodes {
+                black_box(thumb::decode(black_box(opcode)));
+            }
+        })
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            for &opcode in &opcodes {
+                black_box(table[usize::from(black_box(opcode))]);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, arm_decode, thumb_decode);
+criterion_main!(benches);
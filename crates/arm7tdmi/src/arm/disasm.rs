@@ -0,0 +1,459 @@
+//! ARM disassembler.
+
+use std::fmt;
+
+use crate::arm::{
+    decode, CoprocessorOp, DataOpcode, HalfwordKind, HalfwordOffset, Instruction, MsrOperand,
+    Operand2, ShiftAmount, TransferOffset,
+};
+use crate::disasm::{Address, CondSuffix, Imm, Offset, Reg, RegList, Target};
+use crate::ShiftType;
+
+/// Disassembles an ARM opcode.
+///
+/// Branch targets are written relative to the address of the branch
+/// instruction.
+pub fn disassemble(opcode: u32) -> String {
+    decode(opcode).to_string()
+}
+
+/// Disassembles an ARM opcode at `address`.
+///
+/// Branch targets and PC-relative load addresses are resolved to absolute
+/// addresses, accounting for `PC` reading 8 bytes ahead of the instruction.
+pub fn disassemble_at(opcode: u32, address: u32) -> String {
+    decode(opcode).display_at(address).to_string()
+}
+
+/// The offset of `PC` from the executing instruction.
+const PC_OFFSET: u32 = 8;
+
+struct Disasm<'a> {
+    instr: &'a Instruction,
+    address: Option<u32>,
+}
+
+/// Formats the instruction in assembly syntax, with branch targets written
+/// relative to the address of the branch instruction.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Disasm {
+            instr: self,
+            address: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Instruction {
+    /// Returns a value formatting the instruction at `address`, as with
+    /// [`disassemble_at`].
+    pub fn display_at(&self, address: u32) -> impl fmt::Display + '_ {
+        Disasm {
+            instr: self,
+            address: Some(address),
+        }
+    }
+}
+
+impl Disasm<'_> {
+    /// Returns the absolute address of a pre-indexed immediate offset from
+    /// `PC`, if the address of the instruction is known.
+    fn pc_relative(
+        &self,
+        rn: u8,
+        pre_index: bool,
+        writeback: bool,
+        offset: u32,
+        up: bool,
+    ) -> Option<u32> {
+        if rn != 15 || !pre_index || writeback {
+            return None;
+        }
+
+        let pc = self.address?.wrapping_add(PC_OFFSET);
+        Some(if up {
+            pc.wrapping_add(offset)
+        } else {
+            pc.wrapping_sub(offset)
+        })
+    }
+}
+
+impl fmt::Display for Disasm<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.instr {
+            Instruction::BranchExchange { cond, rn } => {
+                write!(f, "BX{} {}", CondSuffix(cond), Reg(rn))
+            }
+            Instruction::Branch { cond, link, offset } => {
+                let mnemonic = if link { "BL" } else { "B" };
+                write!(
+                    f,
+                    "{}{} {}",
+                    mnemonic,
+                    CondSuffix(cond),
+                    Target {
+                        offset: offset + PC_OFFSET as i32,
+                        address: self.address,
+                    }
+                )
+            }
+            Instruction::DataProcessing {
+                cond,
+                opcode,
+                set_flags,
+                rn,
+                rd,
+                operand2,
+            } => {
+                let mnemonic = data_mnemonic(opcode);
+                let cond = CondSuffix(cond);
+                let s = if set_flags { "S" } else { "" };
+                let operand2 = Op2(operand2);
+
+                if opcode.is_test() {
+                    write!(f, "{}{} {}, {}", mnemonic, cond, Reg(rn), operand2)
+                } else if matches!(opcode, DataOpcode::Mov | DataOpcode::Mvn) {
+                    write!(f, "{}{}{} {}, {}", mnemonic, cond, s, Reg(rd), operand2)
+                } else {
+                    write!(
+                        f,
+                        "{}{}{} {}, {}, {}",
+                        mnemonic,
+                        cond,
+                        s,
+                        Reg(rd),
+                        Reg(rn),
+                        operand2
+                    )
+                }
+            }
+            Instruction::Mrs { cond, spsr, rd } => {
+                write!(f, "MRS{} {}, {}", CondSuffix(cond), Reg(rd), psr_name(spsr))
+            }
+            Instruction::Msr {
+                cond,
+                spsr,
+                fields,
+                operand,
+            } => {
+                write!(f, "MSR{} {}_", CondSuffix(cond), psr_name(spsr))?;
+                for (bit, field) in [(3, 'f'), (2, 's'), (1, 'x'), (0, 'c')] {
+                    if fields & (1 << bit) != 0 {
+                        write!(f, "{}", field)?;
+                    }
+                }
+
+                match operand {
+                    MsrOperand::Immediate { value, rotate } => {
+                        write!(f, ", {}", Imm(Operand2::immediate_value(value, rotate)))
+                    }
+                    MsrOperand::Register(rm) => write!(f, ", {}", Reg(rm)),
+                }
+            }
+            Instruction::Multiply {
+                cond,
+                accumulate,
+                set_flags,
+                rd,
+                rn,
+                rs,
+                rm,
+            } => {
+                let mnemonic = if accumulate { "MLA" } else { "MUL" };
+                let s = if set_flags { "S" } else { "" };
+
+                write!(
+                    f,
+                    "{}{}{} {}, {}, {}",
+                    mnemonic,
+                    CondSuffix(cond),
+                    s,
+                    Reg(rd),
+                    Reg(rm),
+                    Reg(rs)
+                )?;
+                if accumulate {
+                    write!(f, ", {}", Reg(rn))?;
+                }
+                Ok(())
+            }
+            Instruction::MultiplyLong {
+                cond,
+                signed,
+                accumulate,
+                set_flags,
+                rd_hi,
+                rd_lo,
+                rs,
+                rm,
+            } => {
+                let sign = if signed { "S" } else { "U" };
+                let mnemonic = if accumulate { "MLAL" } else { "MULL" };
+                let s = if set_flags { "S" } else { "" };
+
+                write!(
+                    f,
+                    "{}{}{}{} {}, {}, {}, {}",
+                    sign,
+                    mnemonic,
+                    CondSuffix(cond),
+                    s,
+                    Reg(rd_lo),
+                    Reg(rd_hi),
+                    Reg(rm),
+                    Reg(rs),
+                )
+            }
+            Instruction::SingleDataSwap {
+                cond,
+                byte,
+                rn,
+                rd,
+                rm,
+            } => {
+                let b = if byte { "B" } else { "" };
+                write!(
+                    f,
+                    "SWP{}{} {}, {}, [{}]",
+                    CondSuffix(cond),
+                    b,
+                    Reg(rd),
+                    Reg(rm),
+                    Reg(rn)
+                )
+            }
+            Instruction::HalfwordDataTransfer {
+                cond,
+                pre_index,
+                up,
+                writeback,
+                load,
+                kind,
+                rn,
+                rd,
+                offset,
+            } => {
+                let mnemonic = if load { "LDR" } else { "STR" };
+                let kind = match kind {
+                    HalfwordKind::Halfword => "H",
+                    HalfwordKind::SignedByte => "SB",
+                    HalfwordKind::SignedHalfword => "SH",
+                };
+                let target = match offset {
+                    HalfwordOffset::Immediate(imm) => {
+                        self.pc_relative(rn, pre_index, writeback, imm.into(), up)
+                    }
+                    _ => None,
+                };
+                let offset = match offset {
+                    HalfwordOffset::Immediate(0) => None,
+                    HalfwordOffset::Immediate(imm) => Some(Addressing::Immediate(imm.into(), up)),
+                    HalfwordOffset::Register(rm) => Some(Addressing::Register(rm, up, None)),
+                };
+
+                write!(f, "{}{}{} {}, ", mnemonic, CondSuffix(cond), kind, Reg(rd))?;
+                match target {
+                    Some(target) => write!(f, "[{}]", Address(target)),
+                    None => write_address(f, rn, pre_index, writeback && pre_index, offset),
+                }
+            }
+            Instruction::SingleDataTransfer {
+                cond,
+                pre_index,
+                up,
+                byte,
+                writeback,
+                load,
+                rn,
+                rd,
+                offset,
+            } => {
+                let mnemonic = if load { "LDR" } else { "STR" };
+                let b = if byte { "B" } else { "" };
+                // Post-indexed transfers with writeback force a user mode access.
+                let t = if !pre_index && writeback { "T" } else { "" };
+                let target = match offset {
+                    TransferOffset::Immediate(imm) => {
+                        self.pc_relative(rn, pre_index, writeback, imm.into(), up)
+                    }
+                    _ => None,
+                };
+                let offset = match offset {
+                    TransferOffset::Immediate(0) => None,
+                    TransferOffset::Immediate(imm) => Some(Addressing::Immediate(imm.into(), up)),
+                    TransferOffset::Register { rm, shift, amount } => {
+                        Some(Addressing::Register(rm, up, Some((shift, amount))))
+                    }
+                };
+
+                write!(
+                    f,
+                    "{}{}{}{} {}, ",
+                    mnemonic,
+                    CondSuffix(cond),
+                    b,
+                    t,
+                    Reg(rd)
+                )?;
+                match target {
+                    Some(target) => write!(f, "[{}]", Address(target)),
+                    None => write_address(f, rn, pre_index, writeback && pre_index, offset),
+                }
+            }
+            Instruction::Undefined { cond } => write!(f, "UND{}", CondSuffix(cond)),
+            Instruction::Coprocessor { cond, op, cp } => {
+                let mnemonic = match op {
+                    CoprocessorOp::Cdp => "CDP",
+                    CoprocessorOp::Ldc => "LDC",
+                    CoprocessorOp::Stc => "STC",
+                    CoprocessorOp::Mrc => "MRC",
+                    CoprocessorOp::Mcr => "MCR",
+                };
+                write!(f, "{}{} p{}", mnemonic, CondSuffix(cond), cp)
+            }
+            Instruction::BlockDataTransfer {
+                cond,
+                mode,
+                s,
+                writeback,
+                load,
+                rn,
+                registers,
+            } => {
+                let mnemonic = if load { "LDM" } else { "STM" };
+                // Use stack aliases for transfers using the stack pointer.
+                let mode = if rn == 13 {
+                    mode.stack_suffix(load)
+                } else {
+                    mode.suffix()
+                };
+                let writeback = if writeback { "!" } else { "" };
+                let s = if s { "^" } else { "" };
+
+                write!(
+                    f,
+                    "{}{}{} {}{}, {}{}",
+                    mnemonic,
+                    CondSuffix(cond),
+                    mode,
+                    Reg(rn),
+                    writeback,
+                    RegList(registers),
+                    s,
+                )
+            }
+            Instruction::SoftwareInterrupt { cond, comment } => {
+                write!(f, "SWI{} {}", CondSuffix(cond), Imm(comment))
+            }
+        }
+    }
+}
+
+fn data_mnemonic(opcode: DataOpcode) -> &'static str {
+    match opcode {
+        DataOpcode::And => "AND",
+        DataOpcode::Eor => "EOR",
+        DataOpcode::Sub => "SUB",
+        DataOpcode::Rsb => "RSB",
+        DataOpcode::Add => "ADD",
+        DataOpcode::Adc => "ADC",
+        DataOpcode::Sbc => "SBC",
+        DataOpcode::Rsc => "RSC",
+        DataOpcode::Tst => "TST",
+        DataOpcode::Teq => "TEQ",
+        DataOpcode::Cmp => "CMP",
+        DataOpcode::Cmn => "CMN",
+        DataOpcode::Orr => "ORR",
+        DataOpcode::Mov => "MOV",
+        DataOpcode::Bic => "BIC",
+        DataOpcode::Mvn => "MVN",
+    }
+}
+
+fn psr_name(spsr: bool) -> &'static str {
+    if spsr {
+        "SPSR"
+    } else {
+        "CPSR"
+    }
+}
+
+/// A data processing second operand.
+struct Op2(Operand2);
+
+impl fmt::Display for Op2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Operand2::Immediate { value, rotate } => {
+                write!(f, "{}", Imm(Operand2::immediate_value(value, rotate)))
+            }
+            Operand2::Register { rm, shift, amount } => {
+                write!(f, "{}", Reg(rm))?;
+                match amount {
+                    ShiftAmount::Immediate(amount) => write_shift(f, shift, amount),
+                    ShiftAmount::Register(rs) => write!(f, ", {} {}", shift, Reg(rs)),
+                }
+            }
+        }
+    }
+}
+
+/// Writes an immediate shift, omitting `LSL #0`.
+fn write_shift(f: &mut fmt::Formatter<'_>, shift: ShiftType, amount: u8) -> fmt::Result {
+    match (shift, amount) {
+        (ShiftType::Lsl, 0) => Ok(()),
+        (ShiftType::Rrx, _) => write!(f, ", {}", shift),
+        (shift, amount) => write!(f, ", {} #{}", shift, amount),
+    }
+}
+
+/// The offset of a load/store address.
+enum Addressing {
+    /// An immediate offset, added if `up` is set.
+    Immediate(u32, bool),
+    /// A register offset, added if `up` is set, with an optional shift.
+    Register(u8, bool, Option<(ShiftType, u8)>),
+}
+
+fn write_address(
+    f: &mut fmt::Formatter<'_>,
+    rn: u8,
+    pre_index: bool,
+    writeback: bool,
+    offset: Option<Addressing>,
+) -> fmt::Result {
+    let write_offset = |f: &mut fmt::Formatter<'_>, offset: Addressing| match offset {
+        Addressing::Immediate(imm, up) => {
+            let imm = imm as i32;
+            write!(f, ", {}", Offset(if up { imm } else { -imm }))
+        }
+        Addressing::Register(rm, up, shift) => {
+            let sign = if up { "" } else { "-" };
+            write!(f, ", {}{}", sign, Reg(rm))?;
+            match shift {
+                Some((shift, amount)) => write_shift(f, shift, amount),
+                None => Ok(()),
+            }
+        }
+    };
+
+    write!(f, "[{}", Reg(rn))?;
+    match offset {
+        Some(offset) if pre_index => {
+            write_offset(f, offset)?;
+            f.write_str("]")?;
+        }
+        Some(offset) => {
+            f.write_str("]")?;
+            write_offset(f, offset)?;
+        }
+        None => f.write_str("]")?,
+    }
+
+    if writeback {
+        f.write_str("!")?;
+    }
+    Ok(())
+}
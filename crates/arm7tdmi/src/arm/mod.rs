@@ -0,0 +1,1063 @@
+//! ARM (32-bit) instruction set.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+
+use crate::bit::BitIndex;
+use crate::{Cond, CpuState, FlagSet, ShiftType};
+
+mod disasm;
+mod opcode;
+
+pub use self::disasm::{disassemble, disassemble_at};
+pub use self::opcode::ArmOpcode;
+
+/// A decoded ARM instruction.
+///
+/// Register operands are stored as register indices in the range `0..16`.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    /// Branch and exchange (`BX`).
+    BranchExchange { cond: Cond, rn: u8 },
+    /// Branch and branch with link (`B`, `BL`).
+    Branch {
+        cond: Cond,
+        /// Branch with link.
+        link: bool,
+        /// Signed byte offset, relative to `PC` (the instruction address + 8).
+        offset: i32,
+    },
+    /// Data processing (`AND`, `EOR`, ..., `MVN`).
+    DataProcessing {
+        cond: Cond,
+        opcode: DataOpcode,
+        /// Set condition codes (S).
+        set_flags: bool,
+        rn: u8,
+        rd: u8,
+        operand2: Operand2,
+    },
+    /// Transfer PSR contents to a register (`MRS`).
+    Mrs {
+        cond: Cond,
+        /// Source is the SPSR of the current mode, rather than the CPSR.
+        spsr: bool,
+        rd: u8,
+    },
+    /// Transfer register contents or an immediate value to a PSR (`MSR`).
+    Msr {
+        cond: Cond,
+        /// Destination is the SPSR of the current mode, rather than the CPSR.
+        spsr: bool,
+        /// Field mask (bit 3: flags, bit 2: status, bit 1: extension,
+        /// bit 0: control).
+        fields: u8,
+        operand: MsrOperand,
+    },
+    /// Multiply and multiply-accumulate (`MUL`, `MLA`).
+    Multiply {
+        cond: Cond,
+        accumulate: bool,
+        /// Set condition codes (S).
+        set_flags: bool,
+        rd: u8,
+        rn: u8,
+        rs: u8,
+        rm: u8,
+    },
+    /// Multiply long and multiply-accumulate long (`UMULL`, `UMLAL`, `SMULL`,
+    /// `SMLAL`).
+    MultiplyLong {
+        cond: Cond,
+        signed: bool,
+        accumulate: bool,
+        /// Set condition codes (S).
+        set_flags: bool,
+        rd_hi: u8,
+        rd_lo: u8,
+        rs: u8,
+        rm: u8,
+    },
+    /// Single data swap (`SWP`).
+    SingleDataSwap {
+        cond: Cond,
+        byte: bool,
+        rn: u8,
+        rd: u8,
+        rm: u8,
+    },
+    /// Halfword and signed data transfer (`LDRH`, `STRH`, `LDRSB`, `LDRSH`).
+    HalfwordDataTransfer {
+        cond: Cond,
+        /// Add offset before transfer (P).
+        pre_index: bool,
+        /// Add offset to base, rather than subtract (U).
+        up: bool,
+        /// Write address back into base (W).
+        writeback: bool,
+        load: bool,
+        kind: HalfwordKind,
+        rn: u8,
+        rd: u8,
+        offset: HalfwordOffset,
+    },
+    /// Single data transfer (`LDR`, `STR`).
+    SingleDataTransfer {
+        cond: Cond,
+        /// Add offset before transfer (P).
+        pre_index: bool,
+        /// Add offset to base, rather than subtract (U).
+        up: bool,
+        /// Transfer a byte, rather than a word (B).
+        byte: bool,
+        /// Write address back into base (W).
+        writeback: bool,
+        load: bool,
+        rn: u8,
+        rd: u8,
+        offset: TransferOffset,
+    },
+    /// Undefined instruction.
+    Undefined { cond: Cond },
+    /// Coprocessor instruction (`CDP`, `LDC`, `STC`, `MRC`, `MCR`).
+    ///
+    /// The GBA has no coprocessors, so these take the undefined instruction
+    /// exception.
+    Coprocessor {
+        cond: Cond,
+        op: CoprocessorOp,
+        /// Coprocessor number.
+        cp: u8,
+    },
+    /// Block data transfer (`LDM`, `STM`).
+    BlockDataTransfer {
+        cond: Cond,
+        /// Addressing mode (P, U).
+        mode: AddressingMode,
+        /// Load PSR or force user mode (S).
+        s: bool,
+        /// Write address back into base (W).
+        writeback: bool,
+        load: bool,
+        rn: u8,
+        /// Register list, bit `n` is set if `Rn` is transferred.
+        registers: u16,
+    },
+    /// Software interrupt (`SWI`).
+    SoftwareInterrupt {
+        cond: Cond,
+        /// Comment field, ignored by the processor.
+        comment: u32,
+    },
+}
+
+/// Data processing opcodes.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessing>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataOpcode {
+    /// `Rd = Rn AND Op2`.
+    And,
+    /// `Rd = Rn XOR Op2`.
+    Eor,
+    /// `Rd = Rn - Op2`.
+    Sub,
+    /// `Rd = Op2 - Rn`.
+    Rsb,
+    /// `Rd = Rn + Op2`.
+    Add,
+    /// `Rd = Rn + Op2 + C`.
+    Adc,
+    /// `Rd = Rn - Op2 + C - 1`.
+    Sbc,
+    /// `Rd = Op2 - Rn + C - 1`.
+    Rsc,
+    /// `Void = Rn AND Op2`.
+    Tst,
+    /// `Void = Rn XOR Op2`.
+    Teq,
+    /// `Void = Rn - Op2`.
+    Cmp,
+    /// `Void = Rn + Op2`.
+    Cmn,
+    /// `Rd = Rn OR Op2`.
+    Orr,
+    /// `Rd = Op2`.
+    Mov,
+    /// `Rd = Rn AND NOT Op2`.
+    Bic,
+    /// `Rd = NOT Op2`.
+    Mvn,
+}
+
+impl DataOpcode {
+    /// Checks if the operation only updates the condition codes, discarding
+    /// the result.
+    #[inline]
+    pub fn is_test(self) -> bool {
+        matches!(
+            self,
+            DataOpcode::Tst | DataOpcode::Teq | DataOpcode::Cmp | DataOpcode::Cmn
+        )
+    }
+
+    /// Checks if the operation is a logical operation, where the carry flag is
+    /// set from the barrel shifter.
+    #[inline]
+    pub fn is_logical(self) -> bool {
+        matches!(
+            self,
+            DataOpcode::And
+                | DataOpcode::Eor
+                | DataOpcode::Tst
+                | DataOpcode::Teq
+                | DataOpcode::Orr
+                | DataOpcode::Mov
+                | DataOpcode::Bic
+                | DataOpcode::Mvn
+        )
+    }
+}
+
+/// The second operand of a data processing instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operand2 {
+    /// An 8-bit immediate, rotated right by an even amount.
+    Immediate {
+        value: u8,
+        /// Rotate amount, in bits (`0..=30`).
+        rotate: u8,
+    },
+    /// A register, shifted by the barrel shifter.
+    Register {
+        rm: u8,
+        shift: ShiftType,
+        amount: ShiftAmount,
+    },
+}
+
+/// The addressing mode of a block data transfer.
+///
+/// # Stack Aliases
+///
+/// ```text
+/// Mode  P  U  LDM  STM
+/// IA    0  1  FD   EA
+/// IB    1  1  ED   FA
+/// DA    0  0  FA   ED
+/// DB    1  0  EA   FD
+/// ```
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemoryblockdatatransferldmstm>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressingMode {
+    /// Increment after (post-increment).
+    IA,
+    /// Increment before (pre-increment).
+    IB,
+    /// Decrement after (post-decrement).
+    DA,
+    /// Decrement before (pre-decrement).
+    DB,
+}
+
+impl AddressingMode {
+    /// Returns the addressing mode for the P (pre-index) and U (up) bits.
+    pub fn from_bits(pre_index: bool, up: bool) -> AddressingMode {
+        match (pre_index, up) {
+            (false, true) => AddressingMode::IA,
+            (true, true) => AddressingMode::IB,
+            (false, false) => AddressingMode::DA,
+            (true, false) => AddressingMode::DB,
+        }
+    }
+
+    /// Checks if the address is updated before each transfer (P).
+    pub fn pre_index(self) -> bool {
+        matches!(self, AddressingMode::IB | AddressingMode::DB)
+    }
+
+    /// Checks if the address increments, rather than decrements (U).
+    pub fn up(self) -> bool {
+        matches!(self, AddressingMode::IA | AddressingMode::IB)
+    }
+
+    /// Returns the suffix of the addressing mode, e.g. `IA`.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            AddressingMode::IA => "IA",
+            AddressingMode::IB => "IB",
+            AddressingMode::DA => "DA",
+            AddressingMode::DB => "DB",
+        }
+    }
+
+    /// Returns the suffix of the stack alias for a load or store, e.g. `FD`
+    /// for `LDMIA` and `STMDB`.
+    pub fn stack_suffix(self, load: bool) -> &'static str {
+        match (self, load) {
+            (AddressingMode::IA, true) | (AddressingMode::DB, false) => "FD",
+            (AddressingMode::IB, true) | (AddressingMode::DA, false) => "ED",
+            (AddressingMode::DA, true) | (AddressingMode::IB, false) => "FA",
+            (AddressingMode::DB, true) | (AddressingMode::IA, false) => "EA",
+        }
+    }
+}
+
+/// Coprocessor operations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CoprocessorOp {
+    /// Coprocessor data operation (`CDP`).
+    Cdp,
+    /// Load coprocessor register from memory (`LDC`).
+    Ldc,
+    /// Store coprocessor register to memory (`STC`).
+    Stc,
+    /// Move from coprocessor to ARM register (`MRC`).
+    Mrc,
+    /// Move from ARM register to coprocessor (`MCR`).
+    Mcr,
+}
+
+/// The amount a register operand is shifted by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShiftAmount {
+    /// An immediate shift amount (`0..=32`).
+    Immediate(u8),
+    /// A shift by the bottom byte of a register.
+    Register(u8),
+}
+
+/// The operand of an `MSR` instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MsrOperand {
+    /// An 8-bit immediate, rotated right by an even amount.
+    Immediate {
+        value: u8,
+        /// Rotate amount, in bits (`0..=30`).
+        rotate: u8,
+    },
+    /// A register.
+    Register(u8),
+}
+
+/// The kind of a halfword or signed data transfer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HalfwordKind {
+    /// Unsigned halfword.
+    Halfword,
+    /// Sign-extended byte.
+    SignedByte,
+    /// Sign-extended halfword.
+    SignedHalfword,
+}
+
+/// The offset of a halfword or signed data transfer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HalfwordOffset {
+    /// An 8-bit immediate offset.
+    Immediate(u8),
+    /// A register offset.
+    Register(u8),
+}
+
+/// The offset of a single data transfer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransferOffset {
+    /// A 12-bit immediate offset.
+    Immediate(u16),
+    /// A register offset, shifted by an immediate amount.
+    Register {
+        rm: u8,
+        shift: ShiftType,
+        /// Shift amount (`0..=32`).
+        amount: u8,
+    },
+}
+
+impl Instruction {
+    /// Returns the size of the instruction in bytes, always 4.
+    #[inline]
+    pub fn length(&self) -> u32 {
+        4
+    }
+
+    /// Returns the state the instruction is executed in, always
+    /// [`CpuState::Arm`].
+    #[inline]
+    pub fn executes_in_state(&self) -> CpuState {
+        CpuState::Arm
+    }
+
+    /// Returns the address of the next instruction, if the instruction at
+    /// `addr` does not branch.
+    #[inline]
+    pub fn next_pc(&self, addr: u32) -> u32 {
+        addr.wrapping_add(self.length())
+    }
+
+    /// Returns the condition the instruction is executed under.
+    pub fn cond(&self) -> Cond {
+        match *self {
+            Instruction::BranchExchange { cond, .. }
+            | Instruction::Branch { cond, .. }
+            | Instruction::DataProcessing { cond, .. }
+            | Instruction::Mrs { cond, .. }
+            | Instruction::Msr { cond, .. }
+            | Instruction::Multiply { cond, .. }
+            | Instruction::MultiplyLong { cond, .. }
+            | Instruction::SingleDataSwap { cond, .. }
+            | Instruction::HalfwordDataTransfer { cond, .. }
+            | Instruction::SingleDataTransfer { cond, .. }
+            | Instruction::Undefined { cond }
+            | Instruction::Coprocessor { cond, .. }
+            | Instruction::BlockDataTransfer { cond, .. }
+            | Instruction::SoftwareInterrupt { cond, .. } => cond,
+        }
+    }
+
+    /// Returns the flags that may be updated by the instruction.
+    ///
+    /// Instructions restoring the CPSR from the SPSR update all flags.
+    pub fn affects_flags(&self) -> FlagSet {
+        match *self {
+            Instruction::DataProcessing {
+                set_flags: false, ..
+            } => FlagSet::NONE,
+            Instruction::DataProcessing { rd: 15, .. } => FlagSet::NZCV,
+            Instruction::DataProcessing {
+                opcode, operand2, ..
+            } if opcode.is_logical() => {
+                if operand2.affects_carry() {
+                    FlagSet::NZC
+                } else {
+                    FlagSet::NZ
+                }
+            }
+            Instruction::DataProcessing { .. } => FlagSet::NZCV,
+            Instruction::Msr {
+                spsr: false,
+                fields,
+                ..
+            } if fields & 0b1000 != 0 => FlagSet::NZCV,
+            Instruction::Multiply {
+                set_flags: true, ..
+            }
+            | Instruction::MultiplyLong {
+                set_flags: true, ..
+            } => FlagSet::NZ,
+            Instruction::BlockDataTransfer {
+                s: true,
+                load: true,
+                registers,
+                ..
+            } if registers & (1 << 15) != 0 => FlagSet::NZCV,
+            _ => FlagSet::NONE,
+        }
+    }
+}
+
+impl Operand2 {
+    /// Returns the value of an immediate operand, after rotation.
+    #[inline]
+    pub fn immediate_value(value: u8, rotate: u8) -> u32 {
+        debug_assert!(
+            rotate < 32 && rotate & 1 == 0,
+            "immediate rotation out of range: {}",
+            rotate
+        );
+
+        u32::from(value).rotate_right(u32::from(rotate))
+    }
+
+    /// Checks if the barrel shifter produces a carry out for the operand,
+    /// rather than passing through the C flag.
+    ///
+    /// Unrotated immediates and `LSL #0` leave C unchanged. Register specified
+    /// shifts also leave C unchanged when shifting by zero, which is only known
+    /// when executed.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessingalu>
+    #[inline]
+    pub fn affects_carry(&self) -> bool {
+        match *self {
+            Operand2::Immediate { rotate, .. } => rotate != 0,
+            Operand2::Register {
+                shift: ShiftType::Lsl,
+                amount: ShiftAmount::Immediate(0),
+                ..
+            } => false,
+            Operand2::Register { .. } => true,
+        }
+    }
+}
+
+/// Decodes an ARM opcode.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+pub fn decode(opcode: u32) -> Instruction {
+    let cond = ArmOpcode(opcode).cond();
+
+    match opcode.bits::<25, 28>() {
+        0b000 | 0b001 => decode_data_processing_space(cond, opcode),
+        // A register offset transfer with bit 4 set is undefined, rather than a
+        // shift by register.
+        0b011 if opcode.bit::<4>() => Instruction::Undefined { cond },
+        0b010 | 0b011 => decode_single_data_transfer(cond, opcode),
+        0b100 => decode_block_data_transfer(cond, opcode),
+        0b101 => Instruction::Branch {
+            cond,
+            link: opcode.bit::<24>(),
+            offset: ((opcode << 8) as i32) >> 6,
+        },
+        0b110 => Instruction::Coprocessor {
+            cond,
+            op: if opcode.bit::<20>() {
+                CoprocessorOp::Ldc
+            } else {
+                CoprocessorOp::Stc
+            },
+            cp: opcode.bits::<8, 12>() as u8,
+        },
+        _ if !opcode.bit::<24>() => Instruction::Coprocessor {
+            cond,
+            op: match (opcode.bit::<4>(), opcode.bit::<20>()) {
+                (false, _) => CoprocessorOp::Cdp,
+                (true, true) => CoprocessorOp::Mrc,
+                (true, false) => CoprocessorOp::Mcr,
+            },
+            cp: opcode.bits::<8, 12>() as u8,
+        },
+        _ => Instruction::SoftwareInterrupt {
+            cond,
+            comment: opcode.bits::<0, 24>(),
+        },
+    }
+}
+
+/// Decodes the opcode space where bits 27-26 are `00`.
+fn decode_data_processing_space(cond: Cond, opcode: u32) -> Instruction {
+    let fields = ArmOpcode(opcode);
+    let immediate = opcode.bit::<25>();
+
+    if !immediate {
+        if opcode & 0x0FFF_FFF0 == 0x012F_FF10 {
+            return Instruction::BranchExchange {
+                cond,
+                rn: fields.rm(),
+            };
+        }
+
+        // Multiplies, swaps and halfword transfers.
+        if opcode.bit::<7>() && opcode.bit::<4>() {
+            return decode_extension_space(cond, opcode);
+        }
+    }
+
+    let opcode_bits = opcode.bits::<21, 25>();
+    let set_flags = opcode.bit::<20>();
+
+    // Test and compare opcodes without the S bit are PSR transfers.
+    if opcode_bits & 0b1100 == 0b1000 && !set_flags {
+        return decode_psr_transfer(cond, opcode);
+    }
+
+    let operand2 = if immediate {
+        Operand2::Immediate {
+            value: fields.immediate(),
+            rotate: fields.rotate(),
+        }
+    } else {
+        let rm = fields.rm();
+        let shift = opcode.bits::<5, 7>();
+
+        if opcode.bit::<4>() {
+            Operand2::Register {
+                rm,
+                shift: shift_type(shift),
+                amount: ShiftAmount::Register(register(opcode.bits::<8, 12>())),
+            }
+        } else {
+            let (shift, amount) = immediate_shift(shift, opcode.bits::<7, 12>() as u8);
+            Operand2::Register {
+                rm,
+                shift,
+                amount: ShiftAmount::Immediate(amount),
+            }
+        }
+    };
+
+    Instruction::DataProcessing {
+        cond,
+        opcode: data_opcode(opcode_bits),
+        set_flags,
+        rn: fields.rn(),
+        rd: fields.rd(),
+        operand2,
+    }
+}
+
+/// Decodes the opcode space where bits 27-25 are `000` and bits 7 and 4 are
+/// set.
+fn decode_extension_space(cond: Cond, opcode: u32) -> Instruction {
+    let fields = ArmOpcode(opcode);
+    match opcode.bits::<5, 7>() {
+        0b00 => match opcode.bits::<23, 25>() {
+            0b00 if !opcode.bit::<22>() => Instruction::Multiply {
+                cond,
+                accumulate: opcode.bit::<21>(),
+                set_flags: opcode.bit::<20>(),
+                rd: fields.rn(),
+                rn: fields.rd(),
+                rs: fields.rs(),
+                rm: fields.rm(),
+            },
+            0b01 => Instruction::MultiplyLong {
+                cond,
+                signed: opcode.bit::<22>(),
+                accumulate: opcode.bit::<21>(),
+                set_flags: opcode.bit::<20>(),
+                rd_hi: fields.rn(),
+                rd_lo: fields.rd(),
+                rs: fields.rs(),
+                rm: fields.rm(),
+            },
+            0b10 if opcode.bits::<20, 22>() == 0b00 && opcode.bits::<8, 12>() == 0 => {
+                Instruction::SingleDataSwap {
+                    cond,
+                    byte: opcode.bit::<22>(),
+                    rn: fields.rn(),
+                    rd: fields.rd(),
+                    rm: fields.rm(),
+                }
+            }
+            _ => Instruction::Undefined { cond },
+        },
+        sh => {
+            let load = opcode.bit::<20>();
+            let kind = match sh {
+                0b01 => HalfwordKind::Halfword,
+                0b10 => HalfwordKind::SignedByte,
+                _ => HalfwordKind::SignedHalfword,
+            };
+
+            // Signed stores are not supported on ARMv4.
+            if !load && kind != HalfwordKind::Halfword {
+                return Instruction::Undefined { cond };
+            }
+
+            let offset = if opcode.bit::<22>() {
+                HalfwordOffset::Immediate(
+                    ((opcode.bits::<8, 12>() << 4) | opcode.bits::<0, 4>()) as u8,
+                )
+            } else {
+                HalfwordOffset::Register(fields.rm())
+            };
+
+            Instruction::HalfwordDataTransfer {
+                cond,
+                pre_index: opcode.bit::<24>(),
+                up: opcode.bit::<23>(),
+                writeback: opcode.bit::<21>(),
+                load,
+                kind,
+                rn: fields.rn(),
+                rd: fields.rd(),
+                offset,
+            }
+        }
+    }
+}
+
+/// Decodes `MRS` and `MSR`.
+fn decode_psr_transfer(cond: Cond, opcode: u32) -> Instruction {
+    let fields = ArmOpcode(opcode);
+    let spsr = opcode.bit::<22>();
+
+    if opcode.bit::<21>() {
+        if opcode.bits::<12, 16>() != 0b1111 {
+            return Instruction::Undefined { cond };
+        }
+
+        let operand = if opcode.bit::<25>() {
+            MsrOperand::Immediate {
+                value: fields.immediate(),
+                rotate: fields.rotate(),
+            }
+        } else {
+            MsrOperand::Register(fields.rm())
+        };
+
+        Instruction::Msr {
+            cond,
+            spsr,
+            fields: opcode.bits::<16, 20>() as u8,
+            operand,
+        }
+    } else {
+        if opcode.bit::<25>() || opcode.bits::<16, 20>() != 0b1111 {
+            return Instruction::Undefined { cond };
+        }
+
+        Instruction::Mrs {
+            cond,
+            spsr,
+            rd: fields.rd(),
+        }
+    }
+}
+
+/// Decodes `LDR` and `STR`.
+fn decode_single_data_transfer(cond: Cond, opcode: u32) -> Instruction {
+    let fields = ArmOpcode(opcode);
+    let offset = if opcode.bit::<25>() {
+        let (shift, amount) = immediate_shift(opcode.bits::<5, 7>(), opcode.bits::<7, 12>() as u8);
+        TransferOffset::Register {
+            rm: fields.rm(),
+            shift,
+            amount,
+        }
+    } else {
+        TransferOffset::Immediate(fields.offset12())
+    };
+
+    Instruction::SingleDataTransfer {
+        cond,
+        pre_index: opcode.bit::<24>(),
+        up: opcode.bit::<23>(),
+        byte: opcode.bit::<22>(),
+        writeback: opcode.bit::<21>(),
+        load: opcode.bit::<20>(),
+        rn: fields.rn(),
+        rd: fields.rd(),
+        offset,
+    }
+}
+
+/// Decodes `LDM` and `STM`.
+fn decode_block_data_transfer(cond: Cond, opcode: u32) -> Instruction {
+    Instruction::BlockDataTransfer {
+        cond,
+        mode: AddressingMode::from_bits(opcode.bit::<24>(), opcode.bit::<23>()),
+        s: opcode.bit::<22>(),
+        writeback: opcode.bit::<21>(),
+        load: opcode.bit::<20>(),
+        rn: ArmOpcode(opcode).rn(),
+        registers: opcode.bits::<0, 16>() as u16,
+    }
+}
+
+/// Decodes a 4-bit data processing opcode field.
+fn data_opcode(bits: u32) -> DataOpcode {
+    debug_assert!(
+        bits < 0x10,
+        "data processing opcode out of range: {:#X}",
+        bits
+    );
+
+    match bits {
+        0x0 => DataOpcode::And,
+        0x1 => DataOpcode::Eor,
+        0x2 => DataOpcode::Sub,
+        0x3 => DataOpcode::Rsb,
+        0x4 => DataOpcode::Add,
+        0x5 => DataOpcode::Adc,
+        0x6 => DataOpcode::Sbc,
+        0x7 => DataOpcode::Rsc,
+        0x8 => DataOpcode::Tst,
+        0x9 => DataOpcode::Teq,
+        0xA => DataOpcode::Cmp,
+        0xB => DataOpcode::Cmn,
+        0xC => DataOpcode::Orr,
+        0xD => DataOpcode::Mov,
+        0xE => DataOpcode::Bic,
+        _ => DataOpcode::Mvn,
+    }
+}
+
+/// Decodes a 4-bit register index field.
+pub(crate) fn register(bits: u32) -> u8 {
+    debug_assert!(bits < 16, "register index out of range: {}", bits);
+
+    bits as u8
+}
+
+/// Decodes a 2-bit shift type field.
+pub(crate) fn shift_type(bits: u32) -> ShiftType {
+    debug_assert!(bits < 0b100, "shift type out of range: {:#b}", bits);
+
+    match bits {
+        0b00 => ShiftType::Lsl,
+        0b01 => ShiftType::Lsr,
+        0b10 => ShiftType::Asr,
+        _ => ShiftType::Ror,
+    }
+}
+
+/// Decodes a 2-bit shift type field with an immediate shift amount,
+/// normalising the special encodings of a zero shift amount.
+pub(crate) fn immediate_shift(bits: u32, amount: u8) -> (ShiftType, u8) {
+    debug_assert!(
+        amount < 32,
+        "immediate shift amount out of range: {}",
+        amount
+    );
+
+    match (shift_type(bits), amount) {
+        (ShiftType::Lsr, 0) => (ShiftType::Lsr, 32),
+        (ShiftType::Asr, 0) => (ShiftType::Asr, 32),
+        (ShiftType::Ror, 0) => (ShiftType::Rrx, 1),
+        (shift, amount) => (shift, amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "register index out of range")]
+    fn register_out_of_range() {
+        let _ = register(16);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "shift type out of range")]
+    fn shift_type_out_of_range() {
+        let _ = shift_type(0b100);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "immediate shift amount out of range")]
+    fn immediate_shift_out_of_range() {
+        let _ = immediate_shift(0b00, 32);
+    }
+
+    #[test]
+    fn addressing_mode() {
+        let cases = [
+            (false, true, AddressingMode::IA, "FD", "EA"),
+            (true, true, AddressingMode::IB, "ED", "FA"),
+            (false, false, AddressingMode::DA, "FA", "ED"),
+            (true, false, AddressingMode::DB, "EA", "FD"),
+        ];
+
+        for (pre_index, up, mode, ldm, stm) in cases {
+            assert_eq!(AddressingMode::from_bits(pre_index, up), mode);
+            assert_eq!(mode.pre_index(), pre_index);
+            assert_eq!(mode.up(), up);
+            assert_eq!(mode.stack_suffix(true), ldm);
+            assert_eq!(mode.stack_suffix(false), stm);
+        }
+    }
+
+    #[test]
+    fn decode_block_data_transfer() {
+        // STMDB sp!, {r4, lr}
+        assert!(matches!(
+            decode(0xE92D_4010),
+            Instruction::BlockDataTransfer {
+                mode: AddressingMode::DB,
+                load: false,
+                writeback: true,
+                rn: 13,
+                registers: 0x4010,
+                ..
+            }
+        ));
+        assert_eq!(disassemble(0xE92D_4010), "STMFD sp!, {r4, lr}");
+        assert_eq!(disassemble(0xE8B0_0003), "LDMIA r0!, {r0, r1}");
+    }
+
+    #[test]
+    fn disassemble_at_address() {
+        // B #+0x120
+        assert_eq!(disassemble(0xEA00_0046), "B #+0x120");
+        assert_eq!(disassemble_at(0xEA00_0046, 0x0800_0000), "B #0x08000120");
+        // BLNE #-0x8
+        assert_eq!(disassemble_at(0x1BFF_FFFC, 0x0800_0010), "BLNE #0x08000008");
+        // LDR r0, [pc, #0x10]
+        assert_eq!(disassemble(0xE59F_0010), "LDR r0, [pc, #0x10]");
+        assert_eq!(
+            disassemble_at(0xE59F_0010, 0x0800_0000),
+            "LDR r0, [#0x08000018]"
+        );
+        // LDRH r0, [pc, #-0x4]
+        assert_eq!(
+            disassemble_at(0xE15F_00B4, 0x0800_0000),
+            "LDRH r0, [#0x08000004]"
+        );
+    }
+
+    #[test]
+    fn display() {
+        let instr = decode(0xE92D_4010);
+        assert_eq!(format!("{instr}"), "STMFD sp!, {r4, lr}");
+        let instr = decode(0xEA00_0046);
+        assert_eq!(format!("{instr}"), "B #+0x120");
+    }
+
+    #[test]
+    fn operand2_affects_carry() {
+        let operand2 = |opcode| match decode(opcode) {
+            Instruction::DataProcessing { operand2, .. } => operand2,
+            instruction => panic!("unexpected instruction: {:?}", instruction),
+        };
+
+        // MOVS r0, #0
+        assert!(!operand2(0xE3B0_0000).affects_carry());
+        // MOVS r0, #0x80000000
+        assert!(operand2(0xE3B0_0102).affects_carry());
+        // MOVS r0, r1
+        assert!(!operand2(0xE1B0_0001).affects_carry());
+        // MOVS r0, r1, LSL #1
+        assert!(operand2(0xE1B0_0081).affects_carry());
+    }
+
+    #[test]
+    fn length() {
+        // MOV r0, #0
+        let instr = decode(0xE3A0_0000);
+        assert_eq!(instr.length(), 4);
+        assert_eq!(instr.next_pc(0x0800_0000), 0x0800_0004);
+    }
+
+    #[test]
+    fn affects_flags() {
+        // ADD r0, r1, r2
+        assert_eq!(decode(0xE081_0002).affects_flags(), FlagSet::NONE);
+        // ADDS r0, r1, r2
+        assert_eq!(decode(0xE091_0002).affects_flags(), FlagSet::NZCV);
+        // CMP r0, r1
+        assert_eq!(decode(0xE150_0001).affects_flags(), FlagSet::NZCV);
+        // MOVS r0, r1
+        assert_eq!(decode(0xE1B0_0001).affects_flags(), FlagSet::NZ);
+        // MOVS r0, r1, LSL #1
+        assert_eq!(decode(0xE1B0_0081).affects_flags(), FlagSet::NZC);
+        // STMFD sp!, {r4, lr}
+        assert_eq!(decode(0xE92D_4010).affects_flags(), FlagSet::NONE);
+    }
+
+    #[test]
+    fn decode_eq() {
+        // EORS r3, r4, r5, LSL #3
+        assert_eq!(
+            decode(0xE034_3185),
+            Instruction::DataProcessing {
+                cond: Cond::AL,
+                opcode: DataOpcode::Eor,
+                set_flags: true,
+                rn: 4,
+                rd: 3,
+                operand2: Operand2::Register {
+                    rm: 5,
+                    shift: ShiftType::Lsl,
+                    amount: ShiftAmount::Immediate(3),
+                },
+            }
+        );
+        // LDR r0, [r1, #0x4]!
+        assert_eq!(
+            decode(0xE5B1_0004),
+            Instruction::SingleDataTransfer {
+                cond: Cond::AL,
+                pre_index: true,
+                up: true,
+                byte: false,
+                writeback: true,
+                load: true,
+                rn: 1,
+                rd: 0,
+                offset: TransferOffset::Immediate(4),
+            }
+        );
+        // STMFD sp!, {r4, lr}
+        assert_eq!(
+            decode(0xE92D_4010),
+            Instruction::BlockDataTransfer {
+                cond: Cond::AL,
+                mode: AddressingMode::DB,
+                s: false,
+                writeback: true,
+                load: false,
+                rn: 13,
+                registers: 0x4010,
+            }
+        );
+        // BNE #+0x100
+        assert_eq!(
+            decode(0x1A00_003E),
+            Instruction::Branch {
+                cond: Cond::NE,
+                link: false,
+                offset: 0xF8,
+            }
+        );
+        assert_ne!(decode(0x1A00_003E), decode(0x0A00_003E));
+    }
+
+    #[test]
+    fn decode_undefined_space() {
+        // UDF #0
+        assert!(matches!(
+            decode(0xE7F0_00F0),
+            Instruction::Undefined { cond: Cond::AL }
+        ));
+        assert!(matches!(
+            decode(0x1610_0010),
+            Instruction::Undefined { cond: Cond::NE }
+        ));
+        // LDR r0, [r0, r1]
+        assert!(matches!(
+            decode(0xE790_0001),
+            Instruction::SingleDataTransfer { .. }
+        ));
+    }
+
+    #[test]
+    fn decode_coprocessor() {
+        // MRC p15, 0, r0, c0, c0, 0
+        assert!(matches!(
+            decode(0xEE10_0F10),
+            Instruction::Coprocessor {
+                cond: Cond::AL,
+                op: CoprocessorOp::Mrc,
+                cp: 15,
+            }
+        ));
+        // CDP p1, 0, c0, c0, c0, 0
+        assert!(matches!(
+            decode(0xEE00_0100),
+            Instruction::Coprocessor {
+                op: CoprocessorOp::Cdp,
+                cp: 1,
+                ..
+            }
+        ));
+        // LDC p2, c0, [r0]
+        assert!(matches!(
+            decode(0xED90_0200),
+            Instruction::Coprocessor {
+                op: CoprocessorOp::Ldc,
+                cp: 2,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,108 @@
+//! Field access on raw ARM opcodes.
+
+use crate::bit::BitIndex;
+use crate::Cond;
+
+/// A raw ARM opcode, providing access to the fields shared by many
+/// instruction formats.
+///
+/// ```text
+/// 31-28  27-20    19-16  15-12  11-8  7-0
+/// Cond   ...      Rn     Rd     Rs    ...   Rm (3-0)
+///                               Rot   Imm
+/// ```
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct ArmOpcode(pub u32);
+
+impl ArmOpcode {
+    /// Returns the condition field (bits 31-28).
+    #[inline]
+    pub fn cond(self) -> Cond {
+        match Cond::try_from(self.0.bits::<28, 32>() as u8) {
+            Ok(cond) => cond,
+            Err(_) => unreachable!("all condition values are valid"),
+        }
+    }
+
+    /// Returns the first operand register field (bits 19-16).
+    #[inline]
+    pub fn rn(self) -> u8 {
+        super::register(self.0.bits::<16, 20>())
+    }
+
+    /// Returns the destination register field (bits 15-12).
+    #[inline]
+    pub fn rd(self) -> u8 {
+        super::register(self.0.bits::<12, 16>())
+    }
+
+    /// Returns the shift or multiply operand register field (bits 11-8).
+    #[inline]
+    pub fn rs(self) -> u8 {
+        super::register(self.0.bits::<8, 12>())
+    }
+
+    /// Returns the second operand register field (bits 3-0).
+    #[inline]
+    pub fn rm(self) -> u8 {
+        super::register(self.0.bits::<0, 4>())
+    }
+
+    /// Returns the 8-bit immediate field (bits 7-0).
+    #[inline]
+    pub fn immediate(self) -> u8 {
+        self.0.bits::<0, 8>() as u8
+    }
+
+    /// Returns the rotation of an 8-bit immediate, twice the rotate field
+    /// (bits 11-8).
+    #[inline]
+    pub fn rotate(self) -> u8 {
+        (self.0.bits::<8, 12>() * 2) as u8
+    }
+
+    /// Returns the 12-bit immediate offset field of a single data transfer
+    /// (bits 11-0).
+    #[inline]
+    pub fn offset12(self) -> u16 {
+        self.0.bits::<0, 12>() as u16
+    }
+}
+
+impl From<u32> for ArmOpcode {
+    #[inline]
+    fn from(opcode: u32) -> ArmOpcode {
+        ArmOpcode(opcode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        // ADDNE r1, r2, r3, LSL r4
+        let opcode = ArmOpcode(0x1082_1413);
+        assert_eq!(opcode.cond(), Cond::NE);
+        assert_eq!(opcode.rn(), 2);
+        assert_eq!(opcode.rd(), 1);
+        assert_eq!(opcode.rs(), 4);
+        assert_eq!(opcode.rm(), 3);
+
+        // MOV r0, #0x3F0
+        let opcode = ArmOpcode(0xE3A0_0E3F);
+        assert_eq!(opcode.cond(), Cond::AL);
+        assert_eq!(opcode.immediate(), 0x3F);
+        assert_eq!(opcode.rotate(), 28);
+
+        // LDR r0, [r1, #0xABC]
+        let opcode = ArmOpcode(0xE591_0ABC);
+        assert_eq!(opcode.offset12(), 0xABC);
+    }
+}
@@ -22,6 +22,28 @@ pub trait BitIndex: Copy {
     /// Sets the value of the bits in the range `START..END`.
     #[must_use]
     fn set_bits<const START: usize, const END: usize>(self, value: Self) -> Self;
+
+    /// Sets the value of the bits in the range `START..END`, truncating
+    /// `value` to the width of the range.
+    ///
+    /// Unlike [`set_bits`], a value too wide for the range is not an error.
+    ///
+    /// [`set_bits`]: BitIndex::set_bits
+    #[must_use]
+    fn replace_bits<const START: usize, const END: usize>(self, value: Self) -> Self;
+
+    /// Returns a mask of the bits in the range `start..end`.
+    ///
+    /// This is the mask selected by [`bits`] before shifting, such that
+    /// `x.bits::<START, END>() << START == x & Self::mask(START, END)`, and the
+    /// mask cleared by [`set_bits`]. An empty range returns `0`, and a range
+    /// covering the whole type returns `Self::MAX`, without overflowing the
+    /// shift.
+    ///
+    /// [`bits`]: BitIndex::bits
+    /// [`set_bits`]: BitIndex::set_bits
+    #[must_use]
+    fn mask(start: usize, end: usize) -> Self;
 }
 
 macro_rules! impl_bits {
@@ -66,13 +88,66 @@ macro_rules! impl_bits {
                     debug_assert!(END <= Self::NBITS);
                     debug_assert_eq!(value >> END, 0);
 
-                    let mask = Self::MAX.bits::<START, END>() << START;
+                    let mask = Self::mask(START, END);
 
                     (self & !mask) | (value << START)
                 }
+
+                #[inline]
+                fn replace_bits<const START: usize, const END: usize>(self, value: Self) -> Self {
+                    debug_assert!(START < END);
+                    debug_assert!(END <= Self::NBITS);
+
+                    let mask = Self::mask(START, END);
+
+                    (self & !mask) | ((value << START) & mask)
+                }
+
+                #[inline]
+                fn mask(start: usize, end: usize) -> Self {
+                    debug_assert!(end <= Self::NBITS);
+
+                    if start >= end {
+                        return 0;
+                    }
+                    (Self::MAX >> (Self::NBITS - (end - start))) << start
+                }
             }
         )*
     };
 }
 
 impl_bits! { u8, u16, u32, u64, u128, usize }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask() {
+        assert_eq!(u32::mask(0, 32), u32::MAX);
+        assert_eq!(u8::mask(0, 8), u8::MAX);
+        assert_eq!(u32::mask(4, 8), 0xF0);
+        assert_eq!(u32::mask(31, 32), 0x8000_0000);
+        assert_eq!(u32::mask(0, 0), 0);
+        assert_eq!(u32::mask(32, 32), 0);
+    }
+
+    #[test]
+    fn mask_matches_bits() {
+        let x = 0xDEAD_BEEFu32;
+        assert_eq!(x.bits::<8, 20>() << 8, x & u32::mask(8, 20));
+        assert_eq!(x.set_bits::<8, 20>(0), x & !u32::mask(8, 20));
+    }
+
+    #[test]
+    fn replace_bits() {
+        let x = 0xDEAD_BEEFu32;
+        assert_eq!(x.replace_bits::<8, 16>(0x12), 0xDEAD_12EF);
+        // Truncated to the width of the range.
+        assert_eq!(x.replace_bits::<8, 16>(0xFF12), 0xDEAD_12EF);
+        assert_eq!(x.replace_bits::<28, 32>(0x1), 0x1EAD_BEEF);
+        assert_eq!(x.replace_bits::<0, 32>(0x1234_5678), 0x1234_5678);
+        assert_eq!(0u8.replace_bits::<0, 5>(0xFF), 0x1F);
+    }
+}
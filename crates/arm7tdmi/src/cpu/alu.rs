@@ -0,0 +1,105 @@
+//! Arithmetic shared by the ARM and THUMB executors.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessingalu>
+
+use crate::arm::DataOpcode;
+
+/// Computes `a + b + carry`, returning the result, carry out and overflow.
+#[inline]
+pub(crate) fn add(a: u32, b: u32, carry: bool) -> (u32, bool, bool) {
+    let wide = u64::from(a) + u64::from(b) + u64::from(carry);
+    let result = wide as u32;
+    let overflow = (!(a ^ b) & (a ^ result)) >> 31 != 0;
+    (result, wide > u64::from(u32::MAX), overflow)
+}
+
+/// Computes `a - b - !carry`, returning the result, carry out and overflow.
+///
+/// The carry out is set when no borrow occurs.
+#[inline]
+pub(crate) fn sub(a: u32, b: u32, carry: bool) -> (u32, bool, bool) {
+    add(a, !b, carry)
+}
+
+/// A logical operation, setting flags from the result rather than from the
+/// arithmetic.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum LogicalOp {
+    And,
+    Eor,
+    Orr,
+    Mov,
+    Bic,
+    Mvn,
+    Tst,
+    Teq,
+}
+
+impl LogicalOp {
+    /// Returns the logical operation performed by a data processing opcode, or
+    /// `None` if the opcode is arithmetic.
+    #[inline]
+    pub(crate) fn from_opcode(opcode: DataOpcode) -> Option<LogicalOp> {
+        Some(match opcode {
+            DataOpcode::And => LogicalOp::And,
+            DataOpcode::Eor => LogicalOp::Eor,
+            DataOpcode::Orr => LogicalOp::Orr,
+            DataOpcode::Mov => LogicalOp::Mov,
+            DataOpcode::Bic => LogicalOp::Bic,
+            DataOpcode::Mvn => LogicalOp::Mvn,
+            DataOpcode::Tst => LogicalOp::Tst,
+            DataOpcode::Teq => LogicalOp::Teq,
+            _ => return None,
+        })
+    }
+}
+
+/// Computes a logical operation.
+///
+/// `TST` and `TEQ` compute the same result as `AND` and `EOR`, which the
+/// caller discards.
+#[inline]
+pub(crate) fn logical(op: LogicalOp, a: u32, b: u32) -> u32 {
+    match op {
+        LogicalOp::And | LogicalOp::Tst => a & b,
+        LogicalOp::Eor | LogicalOp::Teq => a ^ b,
+        LogicalOp::Orr => a | b,
+        LogicalOp::Mov => b,
+        LogicalOp::Bic => a & !b,
+        LogicalOp::Mvn => !b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logical_ops() {
+        let (a, b) = (0xFF00_FF00, 0x0F0F_0F0F);
+
+        assert_eq!(logical(LogicalOp::And, a, b), 0x0F00_0F00);
+        assert_eq!(logical(LogicalOp::Orr, a, b), 0xFF0F_FF0F);
+        assert_eq!(logical(LogicalOp::Mov, a, b), b);
+        assert_eq!(logical(LogicalOp::Bic, a, b), 0xF000_F000);
+        assert_eq!(logical(LogicalOp::Mvn, a, b), 0xF0F0_F0F0);
+        assert_eq!(logical(LogicalOp::Teq, a, b), logical(LogicalOp::Eor, a, b));
+        assert_eq!(logical(LogicalOp::Teq, a, b), 0xF00F_F00F);
+    }
+
+    #[test]
+    fn logical_from_opcode() {
+        assert_eq!(
+            LogicalOp::from_opcode(DataOpcode::Bic),
+            Some(LogicalOp::Bic)
+        );
+        assert_eq!(
+            LogicalOp::from_opcode(DataOpcode::Teq),
+            Some(LogicalOp::Teq)
+        );
+        assert_eq!(LogicalOp::from_opcode(DataOpcode::Cmp), None);
+        assert_eq!(LogicalOp::from_opcode(DataOpcode::Adc), None);
+    }
+}
@@ -0,0 +1,1033 @@
+//! ARM instruction executor.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+
+use crate::arm::{
+    AddressingMode, DataOpcode, HalfwordKind, HalfwordOffset, Instruction, MsrOperand, Operand2,
+    ShiftAmount, TransferOffset,
+};
+use crate::bit::BitIndex;
+use crate::cpu::alu::{self, LogicalOp};
+use crate::cpu::{load16, load32, load_signed16, load_signed8, store16, store32};
+use crate::cpu::{Cpu, Exception};
+use crate::psr::Psr;
+use crate::{Cond, CpuMode, Memory};
+
+impl Cpu {
+    /// Executes a decoded ARM instruction, if its condition is met.
+    ///
+    /// The `NV` condition (`0b1111`) is reserved on ARMv4, instructions using
+    /// it are undefined rather than never executed.
+    pub(crate) fn execute_arm(&mut self, mem: &mut impl Memory, instruction: Instruction) {
+        debug_assert_eq!(
+            instruction.executes_in_state(),
+            self.fetch_state,
+            "ARM instruction executed in wrong state",
+        );
+
+        match instruction.cond() {
+            Cond::Invalid => return self.enter_exception(mem, Exception::Undefined),
+            cond if !cond.matches(self.cpsr) => return,
+            _ => {}
+        }
+
+        match instruction {
+            Instruction::BranchExchange { rn, .. } => self.branch_exchange(mem, self.operand(rn)),
+            Instruction::Branch { link, offset, .. } => self.branch(mem, link, offset),
+            Instruction::DataProcessing {
+                opcode,
+                set_flags,
+                rn,
+                rd,
+                operand2,
+                ..
+            } => self.data_processing(mem, opcode, set_flags, rn, rd, operand2),
+            Instruction::Mrs { spsr, rd, .. } => self.mrs(spsr, rd),
+            Instruction::Msr {
+                spsr,
+                fields,
+                operand,
+                ..
+            } => self.msr(spsr, fields, operand),
+            Instruction::Multiply {
+                accumulate,
+                set_flags,
+                rd,
+                rn,
+                rs,
+                rm,
+                ..
+            } => self.multiply(accumulate, set_flags, rd, rn, rs, rm),
+            Instruction::MultiplyLong {
+                signed,
+                accumulate,
+                set_flags,
+                rd_hi,
+                rd_lo,
+                rs,
+                rm,
+                ..
+            } => self.multiply_long(signed, accumulate, set_flags, rd_hi, rd_lo, rs, rm),
+            Instruction::SingleDataSwap {
+                byte, rn, rd, rm, ..
+            } => self.swap(mem, byte, rn, rd, rm),
+            Instruction::HalfwordDataTransfer {
+                pre_index,
+                up,
+                writeback,
+                load,
+                kind,
+                rn,
+                rd,
+                offset,
+                ..
+            } => {
+                let offset = match offset {
+                    HalfwordOffset::Immediate(imm) => imm.into(),
+                    HalfwordOffset::Register(rm) => self.reg(rm),
+                };
+                let (addr, offset_addr) = self.transfer_address(rn, pre_index, up, offset);
+
+                if load {
+                    let value = match kind {
+                        HalfwordKind::Halfword => load16(mem, addr),
+                        HalfwordKind::SignedByte => load_signed8(mem, addr),
+                        HalfwordKind::SignedHalfword => load_signed16(mem, addr),
+                    };
+                    if writeback || !pre_index {
+                        self.set_reg(rn, offset_addr);
+                    }
+                    self.write_reg(mem, rd, value);
+                } else {
+                    store16(mem, addr, self.store_operand(rd));
+                    if writeback || !pre_index {
+                        self.set_reg(rn, offset_addr);
+                    }
+                }
+            }
+            Instruction::SingleDataTransfer {
+                pre_index,
+                up,
+                byte,
+                writeback,
+                load,
+                rn,
+                rd,
+                offset,
+                ..
+            } => {
+                let offset = match offset {
+                    TransferOffset::Immediate(imm) => imm.into(),
+                    TransferOffset::Register { rm, shift, amount } => {
+                        shift.apply(self.reg(rm), amount.into(), self.cpsr.C()).0
+                    }
+                };
+                let (addr, offset_addr) = self.transfer_address(rn, pre_index, up, offset);
+
+                if load {
+                    let value = if byte {
+                        mem.read8(addr).into()
+                    } else {
+                        load32(mem, addr)
+                    };
+                    if writeback || !pre_index {
+                        self.set_reg(rn, offset_addr);
+                    }
+                    self.write_reg(mem, rd, value);
+                } else {
+                    let value = self.store_operand(rd);
+                    if byte {
+                        mem.write8(addr, value as u8);
+                    } else {
+                        store32(mem, addr, value);
+                    }
+                    if writeback || !pre_index {
+                        self.set_reg(rn, offset_addr);
+                    }
+                }
+            }
+            Instruction::BlockDataTransfer {
+                mode,
+                s,
+                writeback,
+                load,
+                rn,
+                registers,
+                ..
+            } => {
+                self.block_transfer(mem, mode, writeback, load, rn, registers, s);
+            }
+            Instruction::SoftwareInterrupt { comment, .. } => self.software_interrupt(mem, comment),
+            Instruction::Undefined { .. } | Instruction::Coprocessor { .. } => {
+                self.enter_exception(mem, Exception::Undefined)
+            }
+        }
+    }
+
+    /// Branches by a signed offset relative to `PC+8`, saving the return
+    /// address in `LR` if `link` is set.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesbranchandbranchwithlinkbblbxblxswibkpt>
+    fn branch(&mut self, mem: &impl Memory, link: bool, offset: i32) {
+        if link {
+            self.set_reg(14, self.pc());
+        }
+        self.write_reg(mem, 15, self.operand(15).wrapping_add(offset as u32));
+    }
+
+    /// Performs a data processing operation.
+    ///
+    /// Writing `PC` branches to the result, aligned for the state after the
+    /// instruction.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessingalu>
+    fn data_processing(
+        &mut self,
+        mem: &impl Memory,
+        opcode: DataOpcode,
+        set_flags: bool,
+        rn: u8,
+        rd: u8,
+        operand2: Operand2,
+    ) {
+        let (value, carry) = self.shifter_operand(operand2);
+        let rn = match operand2 {
+            // PC has advanced another 4 bytes by the time a register specified
+            // shift is performed.
+            Operand2::Register {
+                amount: ShiftAmount::Register(_),
+                ..
+            } if rn == 15 => self.operand(15).wrapping_add(4),
+            _ => self.operand(rn),
+        };
+
+        let result = self.alu_op(opcode, rn, value, carry, set_flags);
+
+        // Setting flags with PC as the destination restores the CPSR from the
+        // SPSR instead, returning from an exception (e.g. `MOVS pc, lr`).
+        if rd == 15 && set_flags {
+            if let Some(spsr) = self.spsr() {
+                self.set_cpsr(spsr);
+            }
+        }
+        if let Some(result) = result {
+            self.write_reg(mem, rd, result);
+        }
+    }
+
+    /// Returns the value of a data processing second operand, and the carry
+    /// out of the barrel shifter if it affects carry.
+    fn shifter_operand(&self, operand2: Operand2) -> (u32, Option<bool>) {
+        let carry = self.cpsr.C();
+        match operand2 {
+            Operand2::Immediate { value, rotate } => {
+                let value = Operand2::immediate_value(value, rotate);
+                let carry = operand2.affects_carry().then_some(value >> 31 != 0);
+                (value, carry)
+            }
+            Operand2::Register {
+                rm,
+                shift,
+                amount: ShiftAmount::Immediate(amount),
+            } => {
+                let (value, carry) = shift.apply(self.operand(rm), amount.into(), carry);
+                (value, operand2.affects_carry().then_some(carry))
+            }
+            Operand2::Register {
+                rm,
+                shift,
+                amount: ShiftAmount::Register(rs),
+            } => {
+                let value = match rm {
+                    15 => self.operand(15).wrapping_add(4),
+                    rm => self.reg(rm),
+                };
+                match self.reg(rs) & 0xFF {
+                    0 => (value, None),
+                    amount => {
+                        let (value, carry) = shift.apply(value, amount, carry);
+                        (value, Some(carry))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Performs an ALU operation, returning the result unless the operation
+    /// only sets flags.
+    ///
+    /// Logical operations set C from `carry`, the barrel shifter carry out, if
+    /// the shifter affected carry.
+    pub(super) fn alu_op(
+        &mut self,
+        opcode: DataOpcode,
+        a: u32,
+        b: u32,
+        carry: Option<bool>,
+        set_flags: bool,
+    ) -> Option<u32> {
+        let result = if let Some(op) = LogicalOp::from_opcode(opcode) {
+            let result = alu::logical(op, a, b);
+            if set_flags {
+                self.logical_flags(result, carry);
+            }
+            result
+        } else {
+            // Subtraction is performed as `a + !b + carry`, so C is set when no
+            // borrow occurs, and SBC/RSC subtract the inverted carry.
+            let c = self.cpsr.C();
+            let result = match opcode {
+                DataOpcode::Sub | DataOpcode::Cmp => alu::sub(a, b, true),
+                DataOpcode::Rsb => alu::sub(b, a, true),
+                DataOpcode::Add | DataOpcode::Cmn => alu::add(a, b, false),
+                DataOpcode::Adc => alu::add(a, b, c),
+                DataOpcode::Sbc => alu::sub(a, b, c),
+                DataOpcode::Rsc => alu::sub(b, a, c),
+                _ => unreachable!(),
+            };
+            if set_flags {
+                self.set_nzcv(result);
+            }
+            result.0
+        };
+
+        (!opcode.is_test()).then_some(result)
+    }
+
+    /// Multiplies two registers, with an optional accumulate.
+    ///
+    /// The carry flag is left unchanged, the ARM7TDMI sets it to a meaningless
+    /// value.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmultiplyandmultiplyaccumulatemulmla>
+    fn multiply(&mut self, accumulate: bool, set_flags: bool, rd: u8, rn: u8, rs: u8, rm: u8) {
+        let mut result = self.reg(rm).wrapping_mul(self.reg(rs));
+        if accumulate {
+            result = result.wrapping_add(self.reg(rn));
+        }
+
+        if set_flags {
+            self.set_nz(result);
+        }
+        self.set_reg(rd, result);
+    }
+
+    /// Multiplies two registers into a 64-bit result, with an optional
+    /// accumulate.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmultiplyandmultiplyaccumulatemulmla>
+    #[allow(clippy::too_many_arguments)]
+    fn multiply_long(
+        &mut self,
+        signed: bool,
+        accumulate: bool,
+        set_flags: bool,
+        rd_hi: u8,
+        rd_lo: u8,
+        rs: u8,
+        rm: u8,
+    ) {
+        let (a, b) = (self.reg(rm), self.reg(rs));
+        let mut result = if signed {
+            (i64::from(a as i32) * i64::from(b as i32)) as u64
+        } else {
+            u64::from(a) * u64::from(b)
+        };
+        if accumulate {
+            let acc = (u64::from(self.reg(rd_hi)) << 32) | u64::from(self.reg(rd_lo));
+            result = result.wrapping_add(acc);
+        }
+
+        if set_flags {
+            self.cpsr.set_N(result >> 63 != 0);
+            self.cpsr.set_Z(result == 0);
+        }
+        self.set_reg(rd_lo, result as u32);
+        self.set_reg(rd_hi, (result >> 32) as u32);
+    }
+
+    /// Swaps a register with memory.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemorysingledataswapswp>
+    fn swap(&mut self, mem: &mut impl Memory, byte: bool, rn: u8, rd: u8, rm: u8) {
+        let addr = self.reg(rn);
+        let source = self.reg(rm);
+
+        let value = if byte {
+            let value = mem.read8(addr).into();
+            mem.write8(addr, source as u8);
+            value
+        } else {
+            let value = load32(mem, addr);
+            store32(mem, addr, source);
+            value
+        };
+        self.set_reg(rd, value);
+    }
+
+    /// Returns the address of a single transfer, and the base with the offset
+    /// applied for writeback.
+    fn transfer_address(&self, rn: u8, pre_index: bool, up: bool, offset: u32) -> (u32, u32) {
+        let base = self.operand(rn);
+        let offset_addr = if up {
+            base.wrapping_add(offset)
+        } else {
+            base.wrapping_sub(offset)
+        };
+        let addr = if pre_index { offset_addr } else { base };
+        (addr, offset_addr)
+    }
+
+    /// Returns the value of register `n` when stored to memory.
+    #[inline]
+    fn store_operand(&self, n: u8) -> u32 {
+        match n {
+            15 => self.stored_pc(),
+            n => self.reg(n),
+        }
+    }
+
+    /// Transfers a block of registers to or from memory.
+    ///
+    /// An empty register list transfers only `PC`, but moves the base as if
+    /// all 16 registers were transferred. When storing a list including the
+    /// base with writeback, the original base is stored if it is the first
+    /// register in the list, otherwise the written back base is stored. When
+    /// loading the base, the loaded value takes precedence over writeback.
+    ///
+    /// With the `s` bit, and without `PC` in an LDM list, the User bank
+    /// registers are transferred regardless of the current mode. The base is
+    /// still read, and written back, in the current mode; writeback in this
+    /// case is unpredictable on hardware. With `PC` in an LDM list, the `s` bit
+    /// instead restores the CPSR from the SPSR before `PC` is loaded, so that
+    /// the other registers are loaded in the current mode and `PC` is aligned
+    /// for the restored state (e.g. `LDMFD sp!, {r0-r3, pc}^`).
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemoryblockdatatransferldmstm>
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn block_transfer(
+        &mut self,
+        mem: &mut impl Memory,
+        mode: AddressingMode,
+        writeback: bool,
+        load: bool,
+        rn: u8,
+        registers: u16,
+        s: bool,
+    ) {
+        let (registers, size) = match registers {
+            0 => (1 << 15, 0x40),
+            registers => (registers, 4 * registers.count_ones()),
+        };
+
+        let base = self.reg(rn);
+        let mut addr = match mode {
+            AddressingMode::IA => base,
+            AddressingMode::IB => base.wrapping_add(4),
+            AddressingMode::DA => base.wrapping_sub(size).wrapping_add(4),
+            AddressingMode::DB => base.wrapping_sub(size),
+        };
+        let end = if mode.up() {
+            base.wrapping_add(size)
+        } else {
+            base.wrapping_sub(size)
+        };
+
+        let restore_cpsr = s && load && registers & (1 << 15) != 0;
+        let user_bank = s && !restore_cpsr;
+        let current = self.cpsr.mode();
+
+        let list = (0..16).filter(|&n| registers & (1 << n) != 0);
+        if load {
+            if writeback {
+                self.set_reg(rn, end);
+            }
+            if user_bank {
+                self.regs.switch_mode(current, CpuMode::User);
+            }
+            for n in list {
+                // Block transfers ignore the low address bits, without rotating.
+                let value = mem.read32(addr & !0b11);
+                if n == 15 && restore_cpsr {
+                    if let Some(spsr) = self.spsr() {
+                        self.set_cpsr(spsr);
+                    }
+                }
+                self.write_reg(mem, n, value);
+                addr = addr.wrapping_add(4);
+            }
+            if user_bank {
+                self.regs.switch_mode(CpuMode::User, current);
+            }
+        } else {
+            if user_bank {
+                self.regs.switch_mode(current, CpuMode::User);
+            }
+            for (i, n) in list.enumerate() {
+                store32(mem, addr, self.store_operand(n));
+                addr = addr.wrapping_add(4);
+
+                if i == 0 && writeback {
+                    if user_bank {
+                        self.regs.switch_mode(CpuMode::User, current);
+                        self.set_reg(rn, end);
+                        self.regs.switch_mode(current, CpuMode::User);
+                    } else {
+                        self.set_reg(rn, end);
+                    }
+                }
+            }
+            if user_bank {
+                self.regs.switch_mode(CpuMode::User, current);
+            }
+        }
+    }
+
+    /// Transfers a PSR into a register.
+    ///
+    /// User and System mode have no SPSR, reading the SPSR in these modes
+    /// returns the CPSR.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodespsrtransfermrsmsr>
+    fn mrs(&mut self, spsr: bool, rd: u8) {
+        let psr = if spsr {
+            self.spsr().unwrap_or(self.cpsr)
+        } else {
+            self.cpsr
+        };
+        self.set_reg(rd, psr.raw());
+    }
+
+    /// Transfers a register or immediate into the selected fields of a PSR.
+    ///
+    /// The ARM7TDMI only implements the flags (`f`) and control (`c`) fields.
+    /// The control field of the CPSR cannot be written in User mode, and
+    /// writes to the SPSR are ignored in modes without one. Invalid mode bits
+    /// are ignored, keeping the previous mode.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodespsrtransfermrsmsr>
+    fn msr(&mut self, spsr: bool, fields: u8, operand: MsrOperand) {
+        let value = match operand {
+            MsrOperand::Immediate { value, rotate } => Operand2::immediate_value(value, rotate),
+            MsrOperand::Register(rm) => self.operand(rm),
+        };
+
+        let privileged = self.cpsr.mode() != CpuMode::User;
+        let flags = fields & 0b1000 != 0;
+        let control = fields & 0b0001 != 0 && (spsr || privileged);
+
+        if spsr {
+            if let Some(old) = self.spsr() {
+                self.set_spsr(merge_psr(old, value, flags, control));
+            }
+        } else {
+            // Writing the T bit is unpredictable, the pipeline is not flushed
+            // and keeps fetching in the old state.
+            let fetch_state = self.fetch_state;
+            self.set_cpsr(merge_psr(self.cpsr, value, flags, control));
+            self.fetch_state = fetch_state;
+        }
+    }
+}
+
+/// Replaces the flags (bits 31-24) and control (bits 7-0) fields of `old`
+/// with those of `value`, if selected, keeping the old mode if the new mode
+/// bits are invalid.
+fn merge_psr(old: Psr, value: u32, flags: bool, control: bool) -> Psr {
+    let mut raw = old.raw();
+    if flags {
+        raw = raw.replace_bits::<24, 32>(value >> 24);
+    }
+    if control {
+        raw = raw.replace_bits::<0, 8>(value);
+    }
+
+    let mut psr = Psr::from_raw(raw);
+    if let Err(bits) = CpuMode::from_psr_bits(psr.raw() as u8) {
+        log::warn!("ignoring write of invalid cpu mode: {:05b}", bits);
+        psr.set_mode(old.mode());
+    }
+    psr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{arm_program, load_into, FlatMemory};
+    use crate::CpuState;
+
+    /// Runs ARM `opcodes` from address `0x08000000`.
+    fn run(cpu: &mut Cpu, opcodes: &[u32]) {
+        run_in(cpu, &mut FlatMemory::new(), opcodes);
+    }
+
+    /// Runs ARM `opcodes` from address `0x08000000` in `mem`.
+    fn run_in(cpu: &mut Cpu, mem: &mut FlatMemory, opcodes: &[u32]) {
+        load_into(mem, 0x0800_0000, &arm_program(opcodes));
+
+        cpu.set_pc(0x0800_0000);
+        for _ in opcodes {
+            cpu.step(mem);
+        }
+    }
+
+    /// Runs ARM `opcodes` with `r0` and `r1` set to `a` and `b`, and all
+    /// flags cleared.
+    fn run_with(a: u32, b: u32, opcodes: &[u32]) -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        cpu.set_reg(0, a);
+        cpu.set_reg(1, b);
+        run(&mut cpu, opcodes);
+        cpu
+    }
+
+    #[test]
+    fn movs_immediate_carry() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x2000_001F));
+
+        // MOVS r0, #0
+        run(&mut cpu, &[0xE3B0_0000]);
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().Z());
+
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+
+        // MOVS r0, #0x80000000
+        run(&mut cpu, &[0xE3B0_0102]);
+        assert_eq!(cpu.reg(0), 0x8000_0000);
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().N());
+    }
+
+    #[test]
+    fn movs_register_carry() {
+        // MOVS r0, r1
+        let cpu = run_with(0, 0x8000_0000, &[0xE1B0_0001]);
+        assert!(!cpu.cpsr().C());
+
+        // MOVS r0, r1, LSL #1
+        let cpu = run_with(0, 0x8000_0000, &[0xE1B0_0081]);
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().Z());
+    }
+
+    #[test]
+    fn mov_pc() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        cpu.set_reg(14, 0x0800_0100);
+
+        // MOV pc, lr
+        run(&mut cpu, &[0xE1A0_F00E]);
+        assert_eq!(cpu.pc(), 0x0800_0100);
+        assert_eq!(cpu.cpsr(), Psr::from_raw(0x0000_001F));
+    }
+
+    #[test]
+    fn movs_pc_restores_cpsr() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_0092));
+        cpu.set_spsr(Psr::from_raw(0x2000_003F));
+        cpu.set_reg(14, 0x0800_0102);
+
+        // MOVS pc, lr
+        run(&mut cpu, &[0xE1B0_F00E]);
+        assert_eq!(cpu.pc(), 0x0800_0102);
+        assert_eq!(cpu.cpsr(), Psr::from_raw(0x2000_003F));
+        assert_eq!(cpu.cpsr().mode(), CpuMode::System);
+        assert_eq!(cpu.cpsr().state(), CpuState::Thumb);
+    }
+
+    #[test]
+    fn cmp_carry() {
+        // CMP r0, r1
+        let cmp = 0xE150_0001;
+
+        let cpu = run_with(5, 3, &[cmp]);
+        assert!(cpu.cpsr().C());
+        assert!(!cpu.cpsr().Z());
+
+        let cpu = run_with(3, 3, &[cmp]);
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().Z());
+
+        let cpu = run_with(3, 5, &[cmp]);
+        assert!(!cpu.cpsr().C());
+        assert!(cpu.cpsr().N());
+
+        let cpu = run_with(0, 0xFFFF_FFFF, &[cmp]);
+        assert!(!cpu.cpsr().C());
+    }
+
+    #[test]
+    fn subs_zero() {
+        // SUBS r2, r0, r1
+        let cpu = run_with(0, 0, &[0xE050_2001]);
+        assert_eq!(cpu.reg(2), 0);
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().Z());
+        assert!(!cpu.cpsr().N());
+        assert!(!cpu.cpsr().V());
+    }
+
+    #[test]
+    fn rsb_operand_order() {
+        // RSB r2, r0, r1
+        let cpu = run_with(3, 10, &[0xE060_2001]);
+        assert_eq!(cpu.reg(2), 7);
+
+        // RSBS r2, r0, #0
+        let cpu = run_with(1, 0, &[0xE270_2000]);
+        assert_eq!(cpu.reg(2), 0xFFFF_FFFF);
+        assert!(!cpu.cpsr().C());
+        assert!(cpu.cpsr().N());
+    }
+
+    #[test]
+    fn undefined_space() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x6000_001F));
+
+        // UDF #0
+        run(&mut cpu, &[0xE7F0_00F0]);
+        assert_eq!(cpu.pc(), 0x0000_0004);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Undefined);
+        assert_eq!(cpu.reg(14), 0x0800_0004);
+    }
+
+    #[test]
+    fn coprocessor_undefined() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x6000_001F));
+
+        // MRC p15, 0, r0, c0, c0, 0
+        run(&mut cpu, &[0xEE10_0F10]);
+        assert_eq!(cpu.pc(), 0x0000_0004);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Undefined);
+        assert!(cpu.cpsr().irq_disabled());
+        assert_eq!(cpu.reg(14), 0x0800_0004);
+        assert_eq!(cpu.spsr(), Some(Psr::from_raw(0x6000_001F)));
+    }
+
+    #[test]
+    fn nv_condition_undefined() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x6000_001F));
+
+        // MOVNV r0, #1
+        run(&mut cpu, &[0xF3A0_0001]);
+        assert_eq!(cpu.pc(), 0x0000_0004);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Undefined);
+        assert_eq!(cpu.reg(14), 0x0800_0004);
+        assert_eq!(cpu.reg(0), 0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "wrong state")]
+    fn execute_in_thumb_state() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+
+        // MOV r0, #1
+        let instruction = crate::arm::decode(0xE3A0_0001);
+        cpu.execute_arm(&mut FlatMemory::new(), instruction);
+    }
+
+    #[test]
+    fn mrs_cpsr() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x6000_001F));
+
+        // MRS r0, cpsr
+        run(&mut cpu, &[0xE10F_0000]);
+        assert_eq!(cpu.reg(0), 0x6000_001F);
+    }
+
+    #[test]
+    fn mrs_spsr() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_0092));
+        cpu.set_spsr(Psr::from_raw(0x8000_0010));
+
+        // MRS r0, spsr
+        run(&mut cpu, &[0xE14F_0000]);
+        assert_eq!(cpu.reg(0), 0x8000_0010);
+    }
+
+    #[test]
+    fn mrs_spsr_without_spsr() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x2000_001F));
+
+        // MRS r0, spsr
+        run(&mut cpu, &[0xE14F_0000]);
+        assert_eq!(cpu.reg(0), 0x2000_001F);
+    }
+
+    /// Runs the halfword load `opcode` with `r0` set to `addr`, in memory
+    /// holding `0x80 0x7F 0xFE 0x80` at `0x02000000`.
+    fn load_halfword(opcode: u32, addr: u32) -> u32 {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0000, 0x80FE_7F80);
+
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, addr);
+        run_in(&mut cpu, &mut mem, &[opcode]);
+        cpu.reg(2)
+    }
+
+    #[test]
+    fn ldrh_aligned() {
+        // LDRH r2, [r0]
+        assert_eq!(load_halfword(0xE1D0_20B0, 0x0200_0000), 0x0000_7F80);
+        assert_eq!(load_halfword(0xE1D0_20B0, 0x0200_0002), 0x0000_80FE);
+    }
+
+    #[test]
+    fn ldrh_misaligned() {
+        // LDRH r2, [r0]
+        assert_eq!(load_halfword(0xE1D0_20B0, 0x0200_0001), 0x8000_007F);
+        assert_eq!(load_halfword(0xE1D0_20B0, 0x0200_0003), 0xFE00_0080);
+    }
+
+    #[test]
+    fn ldrsh_misaligned() {
+        // LDRSH r2, [r0]
+        assert_eq!(load_halfword(0xE1D0_20F0, 0x0200_0000), 0x0000_7F80);
+        assert_eq!(load_halfword(0xE1D0_20F0, 0x0200_0002), 0xFFFF_80FE);
+        assert_eq!(load_halfword(0xE1D0_20F0, 0x0200_0001), 0x0000_007F);
+        assert_eq!(load_halfword(0xE1D0_20F0, 0x0200_0003), 0xFFFF_FF80);
+    }
+
+    #[test]
+    fn msr_invalid_mode() {
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+
+        // MSR cpsr_c, r0
+        cpu.set_reg(0, 0x0000_00D2);
+        run(&mut cpu, &[0xE121_F000]);
+        assert_eq!(cpu.cpsr().raw(), 0x0000_00D2);
+
+        cpu.set_reg(0, 0x0000_0014);
+        run(&mut cpu, &[0xE121_F000]);
+        assert_eq!(cpu.cpsr().raw(), 0x0000_0012);
+    }
+
+    #[test]
+    fn block_transfer_user_bank() {
+        let mut mem = FlatMemory::new();
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_00DF));
+        cpu.set_reg(13, 0x0300_7F00);
+        cpu.set_reg(14, 0x0800_1234);
+        cpu.set_cpsr(Psr::from_raw(0x0000_00D2));
+        cpu.set_reg(13, 0x0300_7FA0);
+        cpu.set_reg(14, 0x0800_5678);
+        cpu.set_reg(0, 0x0200_0000);
+
+        // STMIA r0, {sp, lr}^
+        run_in(&mut cpu, &mut mem, &[0xE8C0_6000]);
+        assert_eq!(mem.read32(0x0200_0000), 0x0300_7F00);
+        assert_eq!(mem.read32(0x0200_0004), 0x0800_1234);
+
+        mem.write32(0x0200_0000, 0x0300_7E00);
+        mem.write32(0x0200_0004, 0x0800_9ABC);
+
+        // LDMIA r0, {sp, lr}^
+        run_in(&mut cpu, &mut mem, &[0xE8D0_6000]);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Irq);
+        assert_eq!(cpu.reg(13), 0x0300_7FA0);
+        assert_eq!(cpu.reg(14), 0x0800_5678);
+
+        cpu.set_cpsr(Psr::from_raw(0x0000_00DF));
+        assert_eq!(cpu.reg(13), 0x0300_7E00);
+        assert_eq!(cpu.reg(14), 0x0800_9ABC);
+    }
+
+    #[test]
+    fn block_transfer_user_bank_sp_base() {
+        let mut mem = FlatMemory::new();
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_00DF));
+        cpu.set_reg(13, 0x0300_7F00);
+        cpu.set_reg(14, 0x0800_1234);
+        cpu.set_cpsr(Psr::from_raw(0x0000_00D2));
+        cpu.set_reg(13, 0x0300_7FA0);
+        cpu.set_reg(14, 0x0800_5678);
+
+        // STMDB sp, {sp, lr}^
+        run_in(&mut cpu, &mut mem, &[0xE94D_6000]);
+        assert_eq!(cpu.reg(13), 0x0300_7FA0);
+        assert_eq!(cpu.reg(14), 0x0800_5678);
+        assert_eq!(mem.read32(0x0300_7F98), 0x0300_7F00);
+        assert_eq!(mem.read32(0x0300_7F9C), 0x0800_1234);
+
+        // LDMIA sp, {sp, lr}^
+        cpu.set_reg(13, 0x0300_7F98);
+        mem.write32(0x0300_7F98, 0x0300_7E00);
+        run_in(&mut cpu, &mut mem, &[0xE8DD_6000]);
+        assert_eq!(cpu.reg(13), 0x0300_7F98);
+
+        cpu.set_cpsr(Psr::from_raw(0x0000_00DF));
+        assert_eq!(cpu.reg(13), 0x0300_7E00);
+        assert_eq!(cpu.reg(14), 0x0800_1234);
+    }
+
+    #[test]
+    fn ldm_pc_restores_cpsr() {
+        use crate::cpu::exception::Exception;
+
+        let mut mem = FlatMemory::new();
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_00D2));
+        cpu.set_reg(13, 0x0300_7FA0);
+        cpu.set_cpsr(Psr::from_raw(0x2000_003F));
+        cpu.set_reg(13, 0x0300_7F00);
+        cpu.set_reg(0, 0x1234);
+
+        // Interrupted before the THUMB instruction at 0x08000104.
+        cpu.set_pc(0x0800_0104);
+        cpu.enter_exception(&mem, Exception::Irq);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Irq);
+        assert_eq!(cpu.reg(14), 0x0800_0108);
+
+        run_in(
+            &mut cpu,
+            &mut mem,
+            &[
+                0xE24E_E004, // SUB lr, lr, #4
+                0xE92D_4001, // STMFD sp!, {r0, lr}
+                0xE3A0_0001, // MOV r0, #1
+                0xE8FD_8001, // LDMFD sp!, {r0, pc}^
+            ],
+        );
+        assert_eq!(cpu.pc(), 0x0800_0104);
+        assert_eq!(cpu.cpsr(), Psr::from_raw(0x2000_003F));
+        assert_eq!(cpu.cpsr().mode(), CpuMode::System);
+        assert_eq!(cpu.cpsr().state(), CpuState::Thumb);
+        assert_eq!(cpu.reg(0), 0x1234);
+        assert_eq!(cpu.reg(13), 0x0300_7F00);
+
+        cpu.set_cpsr(Psr::from_raw(0x0000_00D2));
+        assert_eq!(cpu.reg(13), 0x0300_7FA0);
+    }
+
+    #[test]
+    fn ldr_pre_index_writeback() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0004, 0x1234_5678);
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, 0x0200_0000);
+
+        // LDR r2, [r0, #4]!
+        run_in(&mut cpu, &mut mem, &[0xE5B0_2004]);
+        assert_eq!(cpu.reg(2), 0x1234_5678);
+        assert_eq!(cpu.reg(0), 0x0200_0004);
+    }
+
+    #[test]
+    fn ldr_post_index() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0004, 0x1234_5678);
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, 0x0200_0004);
+
+        // LDR r2, [r0], #-4
+        run_in(&mut cpu, &mut mem, &[0xE410_2004]);
+        assert_eq!(cpu.reg(2), 0x1234_5678);
+        assert_eq!(cpu.reg(0), 0x0200_0000);
+    }
+
+    #[test]
+    fn ldr_misaligned() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0000, 0x1234_5678);
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, 0x0200_0001);
+
+        // LDR r2, [r0]
+        run_in(&mut cpu, &mut mem, &[0xE590_2000]);
+        assert_eq!(cpu.reg(2), 0x7812_3456);
+
+        cpu.set_reg(0, 0x0200_0003);
+        run_in(&mut cpu, &mut mem, &[0xE590_2000]);
+        assert_eq!(cpu.reg(2), 0x3456_7812);
+    }
+
+    #[test]
+    fn strb() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0000, 0x1234_5678);
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, 0x0200_0000);
+        cpu.set_reg(1, 0xFFFF_FFAB);
+
+        // STRB r1, [r0, #1]
+        run_in(&mut cpu, &mut mem, &[0xE5C0_1001]);
+        assert_eq!(mem.read32(0x0200_0000), 0x1234_AB78);
+        assert_eq!(cpu.reg(0), 0x0200_0000);
+    }
+
+    #[test]
+    fn data_processing_opcodes() {
+        use DataOpcode::*;
+
+        // (opcode, r0, r1, result, NZCV), with the carry flag initially clear.
+        let cases = [
+            (And, 0xF0F0_0000, 0xFF00_0000, Some(0xF000_0000), 0b1000),
+            (Eor, 0x0000_00FF, 0x0000_00FF, Some(0x0000_0000), 0b0100),
+            (Sub, 5, 3, Some(2), 0b0010),
+            (Rsb, 5, 3, Some(0xFFFF_FFFE), 0b1000),
+            (Add, 0x7FFF_FFFF, 1, Some(0x8000_0000), 0b1001),
+            (Adc, 0xFFFF_FFFF, 1, Some(0x0000_0000), 0b0110),
+            (Sbc, 5, 3, Some(1), 0b0010),
+            (Rsc, 5, 3, Some(0xFFFF_FFFD), 0b1000),
+            (Tst, 0x0000_00F0, 0x0000_000F, None, 0b0100),
+            (Teq, 0x8000_0000, 0x0000_0000, None, 0b1000),
+            (Cmp, 3, 5, None, 0b1000),
+            (Cmn, 0xFFFF_FFFF, 1, None, 0b0110),
+            (Orr, 0x0000_000F, 0x0000_00F0, Some(0x0000_00FF), 0b0000),
+            (Mov, 0x1234_5678, 0, Some(0x0000_0000), 0b0100),
+            (Bic, 0x0000_00FF, 0x0000_000F, Some(0x0000_00F0), 0b0000),
+            (Mvn, 0x1234_5678, 0, Some(0xFFFF_FFFF), 0b1000),
+        ];
+
+        for (opcode, a, b, result, flags) in cases {
+            // <opcode>S r2, r0, r1
+            let op = 0xE010_2001 | (opcode as u32) << 21;
+            let mut cpu = run_with(a, b, &[op]);
+
+            assert_eq!(cpu.reg(2), result.unwrap_or(0), "{:?}", opcode);
+            assert_eq!(cpu.cpsr().raw() >> 28, flags, "{:?}", opcode);
+
+            // Without the S bit, the flags are unchanged.
+            if let Some(result) = result {
+                cpu = run_with(a, b, &[op & !(1 << 20)]);
+                assert_eq!(cpu.reg(2), result, "{:?}", opcode);
+                assert_eq!(cpu.cpsr().raw() >> 28, 0, "{:?}", opcode);
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Exception handling.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#armcpuexceptions>
+
+use crate::cpu::Cpu;
+use crate::{CpuMode, CpuState, Memory};
+
+/// A CPU exception.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armcpuexceptions>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Exception {
+    /// Reset, vector `0x00`.
+    Reset,
+    /// Undefined instruction, vector `0x04`.
+    Undefined,
+    /// Software interrupt (`SWI`), vector `0x08`.
+    SoftwareInterrupt,
+    /// Prefetch abort, vector `0x0C`.
+    PrefetchAbort,
+    /// Data abort, vector `0x10`.
+    DataAbort,
+    /// Normal interrupt (IRQ), vector `0x18`.
+    Irq,
+    /// Fast interrupt (FIQ), vector `0x1C`.
+    Fiq,
+}
+
+/// A hook called when executing a software interrupt, before entering the
+/// exception.
+///
+/// The hook is passed the comment field of the `SWI` instruction, 24 bits in
+/// ARM state or 8 bits in THUMB state, and `PC` holds the address of the
+/// instruction after the `SWI`. Returning `true` marks the interrupt as
+/// handled, skipping the exception, e.g. for high-level emulation of BIOS
+/// functions.
+pub type SwiHook = fn(&mut Cpu, u32) -> bool;
+
+impl Exception {
+    /// Returns the address of the exception vector.
+    ///
+    /// The vector at `0x14` is reserved, it was used for address exceptions
+    /// on 26-bit ARM cores.
+    pub fn vector(self) -> u32 {
+        match self {
+            Exception::Reset => 0x00,
+            Exception::Undefined => 0x04,
+            Exception::SoftwareInterrupt => 0x08,
+            Exception::PrefetchAbort => 0x0C,
+            Exception::DataAbort => 0x10,
+            Exception::Irq => 0x18,
+            Exception::Fiq => 0x1C,
+        }
+    }
+
+    /// Returns the mode the exception is handled in.
+    pub fn mode(self) -> CpuMode {
+        match self {
+            Exception::Reset | Exception::SoftwareInterrupt => CpuMode::Supervisor,
+            Exception::Undefined => CpuMode::Undefined,
+            Exception::PrefetchAbort | Exception::DataAbort => CpuMode::Abort,
+            Exception::Irq => CpuMode::Irq,
+            Exception::Fiq => CpuMode::Fiq,
+        }
+    }
+}
+
+impl Cpu {
+    /// Enters an exception, saving the CPSR and return address in the banked
+    /// registers of the exception mode and jumping to its vector.
+    ///
+    /// This is expected to be called after an instruction has been fetched,
+    /// while `PC` holds the address of the next instruction.
+    pub(crate) fn enter_exception(&mut self, mem: &impl Memory, exception: Exception) {
+        let return_address = match exception {
+            Exception::Reset
+            | Exception::Undefined
+            | Exception::SoftwareInterrupt
+            | Exception::PrefetchAbort => self.pc(),
+            Exception::DataAbort | Exception::Irq | Exception::Fiq => self.pc().wrapping_add(4),
+        };
+
+        let old = self.cpsr;
+        let mut cpsr = old;
+        cpsr.set_mode(exception.mode());
+        cpsr.set_state(CpuState::Arm);
+        cpsr.set_irq_disabled(true);
+        if matches!(exception, Exception::Reset | Exception::Fiq) {
+            cpsr.set_fiq_disabled(true);
+        }
+
+        self.set_cpsr(cpsr);
+        self.set_spsr(old);
+        self.set_reg(14, return_address);
+        self.set_pc(exception.vector());
+        self.flush_pipeline(mem);
+    }
+}
+
+impl Cpu {
+    /// Executes a software interrupt with the given comment field.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#biosfunctions>
+    pub(crate) fn software_interrupt(&mut self, mem: &impl Memory, comment: u32) {
+        if let Some(hook) = self.swi_hook {
+            if hook(self, comment) {
+                return;
+            }
+        }
+        self.enter_exception(mem, Exception::SoftwareInterrupt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::FlatMemory;
+
+    #[test]
+    fn vectors() {
+        let vectors = [
+            (Exception::Reset, 0x00),
+            (Exception::Undefined, 0x04),
+            (Exception::SoftwareInterrupt, 0x08),
+            (Exception::PrefetchAbort, 0x0C),
+            (Exception::DataAbort, 0x10),
+            (Exception::Irq, 0x18),
+            (Exception::Fiq, 0x1C),
+        ];
+
+        for (exception, vector) in vectors {
+            assert_eq!(exception.vector(), vector, "{:?}", exception);
+
+            let mut cpu = Cpu::new();
+            cpu.enter_exception(&FlatMemory::new(), exception);
+            assert_eq!(cpu.pc(), vector, "{:?}", exception);
+            assert_eq!(cpu.cpsr().mode(), exception.mode(), "{:?}", exception);
+        }
+    }
+}
@@ -0,0 +1,712 @@
+//! CPU core.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#armcpureference>
+
+use crate::psr::Psr;
+use crate::registers::Registers;
+use crate::{CpuMode, CpuState, Memory};
+
+mod alu;
+mod arm;
+mod exception;
+mod thumb;
+
+pub use self::exception::{Exception, SwiHook};
+
+/// An ARM7TDMI CPU.
+///
+/// Between steps, `PC` holds the address of the next instruction to execute.
+/// While executing an instruction, reads of `PC` as an operand return the
+/// address of the instruction plus 8 in ARM state, or plus 4 in THUMB state,
+/// as a result of the pipeline.
+///
+/// Cycles are counted without wait states, so sequential and non-sequential
+/// accesses both take a single cycle, and internal cycles are not counted.
+#[derive(Clone, Debug)]
+pub struct Cpu {
+    regs: Registers,
+    cpsr: Psr,
+    prefetch: u32,
+    /// The state instructions are fetched in, set from the CPSR when the
+    /// pipeline is flushed.
+    fetch_state: CpuState,
+    cycles: u64,
+    swi_hook: Option<SwiHook>,
+}
+
+impl Cpu {
+    /// Creates a CPU in the reset state.
+    ///
+    /// The CPU starts executing at address `0x00000000` in ARM state, in
+    /// supervisor mode with interrupts disabled.
+    pub fn new() -> Cpu {
+        let mut cpsr = Psr::default();
+        cpsr.set_mode(CpuMode::Supervisor);
+        cpsr.set_state(CpuState::Arm);
+        cpsr.set_irq_disabled(true);
+        cpsr.set_fiq_disabled(true);
+
+        Cpu {
+            regs: Registers::default(),
+            cpsr,
+            prefetch: 0,
+            fetch_state: CpuState::Arm,
+            cycles: 0,
+            swi_hook: None,
+        }
+    }
+
+    /// Sets up the state left by the BIOS boot sequence, for starting execution
+    /// at `entry` without running the BIOS.
+    ///
+    /// The registers are cleared, the stack pointers of the User/System, IRQ
+    /// and Supervisor modes are set to their default values, and the CPU is
+    /// left in System mode in ARM state with interrupts enabled.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#biosramusage>
+    pub fn hle_boot(&mut self, mem: &impl Memory, entry: u32) {
+        const STACKS: [(CpuMode, u32); 3] = [
+            (CpuMode::Supervisor, 0x0300_7FE0),
+            (CpuMode::Irq, 0x0300_7FA0),
+            (CpuMode::System, 0x0300_7F00),
+        ];
+
+        self.regs = Registers::default();
+        for (mode, sp) in STACKS {
+            let mut cpsr = Psr::default();
+            cpsr.set_mode(mode);
+            cpsr.set_state(CpuState::Arm);
+            self.set_cpsr(cpsr);
+            self.set_reg(13, sp);
+        }
+        self.set_pc(entry);
+        self.flush_pipeline(mem);
+    }
+
+    /// Returns the register file.
+    #[inline]
+    pub fn registers(&self) -> &Registers {
+        &self.regs
+    }
+
+    /// Returns the value of register `n` in the current mode.
+    #[inline]
+    pub fn reg(&self, n: u8) -> u32 {
+        self.regs.get(n)
+    }
+
+    /// Sets the value of register `n` in the current mode.
+    #[inline]
+    pub fn set_reg(&mut self, n: u8, value: u32) {
+        self.regs.set(n, value);
+    }
+
+    /// Returns the address of the next instruction to execute.
+    #[inline]
+    pub fn pc(&self) -> u32 {
+        self.regs.get(15)
+    }
+
+    /// Sets the address of the next instruction to execute.
+    #[inline]
+    pub fn set_pc(&mut self, pc: u32) {
+        self.regs.set(15, pc);
+    }
+
+    /// Returns the word most recently fetched by the pipeline, two
+    /// instructions ahead of the executing instruction.
+    ///
+    /// This is the value seen when reading from unmapped memory (open bus).
+    #[inline]
+    pub fn prefetch(&self) -> u32 {
+        self.prefetch
+    }
+
+    /// Returns the number of cycles executed.
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Sets the hook called when executing a software interrupt, see
+    /// [`SwiHook`].
+    #[inline]
+    pub fn set_swi_hook(&mut self, hook: Option<SwiHook>) {
+        self.swi_hook = hook;
+    }
+
+    /// Returns the current program status register.
+    #[inline]
+    pub fn cpsr(&self) -> Psr {
+        self.cpsr
+    }
+
+    /// Sets the current program status register, switching register banks if
+    /// the mode changes.
+    ///
+    /// Instructions are fetched in the state of the new CPSR.
+    pub fn set_cpsr(&mut self, cpsr: Psr) {
+        self.regs.switch_mode(self.cpsr.mode(), cpsr.mode());
+        self.cpsr = cpsr;
+        self.fetch_state = cpsr.state();
+    }
+
+    /// Switches to `mode`, swapping in its banked registers.
+    ///
+    /// Transitions that usually indicate a bug are logged as warnings, such as
+    /// leaving User mode or entering FIQ mode with FIQs unmasked.
+    pub fn set_mode_checked(&mut self, mode: CpuMode) {
+        if let Some(reason) = self.suspicious_transition(mode) {
+            log::warn!(
+                "suspicious cpu mode transition from {} to {}: {}",
+                self.cpsr.mode(),
+                mode,
+                reason
+            );
+        }
+
+        let mut cpsr = self.cpsr;
+        cpsr.set_mode(mode);
+        self.set_cpsr(cpsr);
+    }
+
+    /// Returns the reason a transition to `mode` is suspicious, if it is.
+    ///
+    /// A transition is suspicious if it leaves User mode, which is only
+    /// possible through an exception, or if it enters the IRQ or FIQ mode
+    /// with that interrupt unmasked, allowing it to re-enter its own handler.
+    fn suspicious_transition(&self, mode: CpuMode) -> Option<&'static str> {
+        match (self.cpsr.mode(), mode) {
+            (CpuMode::User, CpuMode::User) => None,
+            (CpuMode::User, _) => Some("privileged mode change from user mode"),
+            (_, CpuMode::Fiq) if !self.cpsr.fiq_disabled() => {
+                Some("entering FIQ with FIQ unmasked")
+            }
+            (_, CpuMode::Irq) if !self.cpsr.irq_disabled() => {
+                Some("entering IRQ with IRQ unmasked")
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the current state, from the T bit of the CPSR.
+    #[inline]
+    pub fn state(&self) -> CpuState {
+        self.cpsr.state()
+    }
+
+    /// Sets the current state, by writing the T bit of the CPSR.
+    ///
+    /// Instructions are fetched in the new state, but `PC` is not realigned
+    /// for it.
+    #[inline]
+    pub fn set_state(&mut self, state: CpuState) {
+        self.cpsr.set_state(state);
+        self.fetch_state = state;
+    }
+
+    /// Returns the saved program status register of the current mode, or
+    /// `None` in User and System mode.
+    #[inline]
+    pub fn spsr(&self) -> Option<Psr> {
+        self.regs.spsr(self.cpsr.mode())
+    }
+
+    /// Sets the saved program status register of the current mode, ignored in
+    /// User and System mode.
+    #[inline]
+    pub fn set_spsr(&mut self, spsr: Psr) {
+        self.regs.set_spsr(self.cpsr.mode(), spsr);
+    }
+
+    /// Checks that the T bit of the CPSR matches the state instructions are
+    /// fetched in, and that `PC` is aligned for that state, in debug builds.
+    ///
+    /// The fetch state only follows the T bit when the pipeline is flushed,
+    /// by a branch, an exception or a write to `PC`. Changing the T bit any
+    /// other way, e.g. with `MSR`, leaves the CPU fetching in the old state
+    /// and decoding in the new one.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the states differ, or `PC` is misaligned.
+    #[inline]
+    pub fn assert_state_consistent(&self) {
+        debug_assert_eq!(
+            self.cpsr.state(),
+            self.fetch_state,
+            "CPSR state differs from fetch state",
+        );
+        debug_assert!(
+            self.pc() & !self.fetch_state.pc_alignment_mask() == 0,
+            "PC {:#010X} misaligned for {} state",
+            self.pc(),
+            self.fetch_state,
+        );
+    }
+
+    /// Fetches the instruction at `PC` for the fetch state, and advances `PC`
+    /// past it.
+    ///
+    /// The word two instructions ahead is fetched into the pipeline, taking a
+    /// sequential cycle. A THUMB opcode is zero-extended.
+    pub fn fetch(&mut self, mem: &impl Memory) -> u32 {
+        let state = self.fetch_state;
+        let pc = self.pc() & state.pc_alignment_mask();
+        let size = state.instruction_size();
+        let read = |addr| match state {
+            CpuState::Arm => mem.read32(addr),
+            CpuState::Thumb => mem.read16(addr).into(),
+        };
+
+        let opcode = read(pc);
+        self.prefetch = read(pc.wrapping_add(2 * size));
+        self.cycles += 1;
+        self.set_pc(pc.wrapping_add(size));
+
+        opcode
+    }
+
+    /// Flushes the pipeline after a write to `PC`.
+    ///
+    /// Refilling the pipeline from the new `PC` takes a non-sequential and a
+    /// sequential cycle, before the fetch of the next instruction, so a
+    /// branch takes 2S+1N cycles in total. The prefetched word is refilled
+    /// with the word two instructions after the new `PC`, in the state of the
+    /// CPSR.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#arminstructionsummary>
+    pub fn flush_pipeline(&mut self, mem: &impl Memory) {
+        let state = self.cpsr.state();
+        self.fetch_state = state;
+        let addr = self.pc().wrapping_add(2 * state.instruction_size());
+        self.prefetch = match state {
+            CpuState::Arm => mem.read32(addr),
+            CpuState::Thumb => mem.read16(addr).into(),
+        };
+        self.cycles += 2;
+    }
+
+    /// Executes a single instruction.
+    ///
+    /// The instruction is decoded in the state of the CPSR. In debug builds,
+    /// this panics if it differs from the state the instruction was fetched
+    /// in, see [`Cpu::assert_state_consistent`].
+    pub fn step(&mut self, mem: &mut impl Memory) {
+        let opcode = self.fetch(mem);
+        match self.cpsr.state() {
+            CpuState::Arm => self.execute_arm(mem, crate::arm::decode(opcode)),
+            CpuState::Thumb => self.execute_thumb(mem, crate::thumb::decode(opcode as u16)),
+        }
+    }
+
+    /// Executes `count` instructions.
+    pub fn step_n(&mut self, mem: &mut impl Memory, count: u64) {
+        for _ in 0..count {
+            self.step(mem);
+        }
+    }
+
+    /// Executes instructions until `pred` returns `true`, or at least `budget`
+    /// cycles have been executed.
+    ///
+    /// The predicate is checked before each instruction, so a breakpoint on
+    /// `PC` stops before executing the instruction at that address. An
+    /// instruction is never interrupted, so the last instruction may overrun
+    /// the budget. Returns `true` if the predicate was met.
+    pub fn run_until(
+        &mut self,
+        mem: &mut impl Memory,
+        budget: u64,
+        pred: impl Fn(&Cpu) -> bool,
+    ) -> bool {
+        let end = self.cycles.saturating_add(budget);
+        while self.cycles < end {
+            if pred(self) {
+                return true;
+            }
+            self.step(mem);
+        }
+        pred(self)
+    }
+
+    /// Sets the N and Z flags from `result`.
+    #[inline]
+    fn set_nz(&mut self, result: u32) {
+        self.cpsr.set_N(result >> 31 != 0);
+        self.cpsr.set_Z(result == 0);
+    }
+
+    /// Sets the N and Z flags from the result of a logical operation, and C
+    /// from the barrel shifter carry out if the shifter affected carry.
+    #[inline]
+    fn logical_flags(&mut self, result: u32, carry: Option<bool>) {
+        self.set_nz(result);
+        if let Some(carry) = carry {
+            self.cpsr.set_C(carry);
+        }
+    }
+
+    /// Sets the N, Z, C and V flags from an arithmetic operation.
+    #[inline]
+    fn set_nzcv(&mut self, (result, carry, overflow): (u32, bool, bool)) {
+        self.set_nz(result);
+        self.cpsr.set_C(carry);
+        self.cpsr.set_V(overflow);
+    }
+
+    /// Writes the result of an instruction to register `n`.
+    ///
+    /// Writes to `PC` are aligned to the instruction size of the current state.
+    #[inline]
+    fn write_reg(&mut self, mem: &impl Memory, n: u8, value: u32) {
+        match n {
+            15 => {
+                self.set_pc(value & self.cpsr.state().pc_alignment_mask());
+                self.flush_pipeline(mem);
+            }
+            n => self.set_reg(n, value),
+        }
+    }
+
+    /// Branches to `target`, switching to THUMB state if bit 0 is set.
+    fn branch_exchange(&mut self, mem: &impl Memory, target: u32) {
+        if target & 1 != 0 {
+            self.cpsr.set_state(CpuState::Thumb);
+            self.set_pc(target & !0b1);
+        } else {
+            self.cpsr.set_state(CpuState::Arm);
+            self.set_pc(target & !0b11);
+        }
+        self.flush_pipeline(mem);
+    }
+
+    /// Returns the value of `PC` when stored to memory, one instruction ahead
+    /// of the value read as an operand.
+    #[inline]
+    fn stored_pc(&self) -> u32 {
+        match self.cpsr.state() {
+            CpuState::Arm => self.pc().wrapping_add(8),
+            CpuState::Thumb => self.pc().wrapping_add(4),
+        }
+    }
+
+    /// Returns the value of register `n` when read as an operand while
+    /// executing an instruction.
+    #[inline]
+    fn operand(&self, n: u8) -> u32 {
+        match n {
+            15 => match self.cpsr.state() {
+                CpuState::Arm => self.pc().wrapping_add(4),
+                CpuState::Thumb => self.pc().wrapping_add(2),
+            },
+            n => self.regs.get(n),
+        }
+    }
+}
+
+/// Loads a word.
+///
+/// A misaligned load reads the word at the aligned address, rotated right by 8
+/// bits for each byte of misalignment, so the addressed byte ends up in the low
+/// byte.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemorysingledatatransferldrstrpld>
+#[inline]
+fn load32(mem: &impl Memory, addr: u32) -> u32 {
+    mem.read32(addr & !0b11).rotate_right(8 * (addr & 0b11))
+}
+
+/// Loads a zero-extended halfword.
+///
+/// A misaligned load reads the halfword at the aligned address, rotated right
+/// by 8 bits, so the addressed byte ends up in the low byte.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemoryhalfworddoublewordandsignedd>
+#[inline]
+fn load16(mem: &impl Memory, addr: u32) -> u32 {
+    u32::from(mem.read16(addr & !0b1)).rotate_right(8 * (addr & 0b1))
+}
+
+/// Loads a sign-extended halfword.
+///
+/// A misaligned load instead loads a sign-extended byte from the address.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesmemoryhalfworddoublewordandsignedd>
+#[inline]
+fn load_signed16(mem: &impl Memory, addr: u32) -> u32 {
+    if addr & 0b1 != 0 {
+        load_signed8(mem, addr)
+    } else {
+        mem.read16(addr) as i16 as u32
+    }
+}
+
+/// Loads a sign-extended byte.
+#[inline]
+fn load_signed8(mem: &impl Memory, addr: u32) -> u32 {
+    mem.read8(addr) as i8 as u32
+}
+
+/// Stores a word to a word aligned address.
+#[inline]
+fn store32(mem: &mut impl Memory, addr: u32, value: u32) {
+    mem.write32(addr & !0b11, value);
+}
+
+/// Stores the low halfword of `value` to a halfword aligned address.
+///
+/// A misaligned store is unpredictable; on the GBA it stores to the aligned
+/// address.
+#[inline]
+fn store16(mem: &mut impl Memory, addr: u32, value: u32) {
+    mem.write16(addr & !0b1, value as u16);
+}
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        Cpu::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{arm_program, capture_logs, load_into, thumb_program, FlatMemory};
+
+    /// Loads a THUMB program counting `r0` down from 3 to 0, followed by
+    /// `MOV r1, #1` at `0x08000006`.
+    fn countdown() -> (Cpu, FlatMemory) {
+        let mut mem = FlatMemory::new();
+        let program: [u16; 4] = [
+            0x2003, // MOV r0, #3
+            0x3801, // SUB r0, #1
+            0xD1FD, // BNE #-0x2
+            0x2101, // MOV r1, #1
+        ];
+        load_into(&mut mem, 0x0800_0000, &thumb_program(&program));
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0000);
+        (cpu, mem)
+    }
+
+    #[test]
+    fn hle_boot() {
+        let mut cpu = Cpu::new();
+        cpu.set_reg(0, 0x1234);
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0800_0008, 0xEAFF_FFFE);
+        cpu.hle_boot(&mem, 0x0800_0000);
+
+        assert_eq!(cpu.pc(), 0x0800_0000);
+        assert_eq!(cpu.prefetch(), 0xEAFF_FFFE);
+        assert_eq!(cpu.reg(0), 0);
+        assert_eq!(cpu.cpsr(), Psr::from_raw(0x0000_001F));
+        assert_eq!(cpu.reg(13), 0x0300_7F00);
+
+        for (mode, sp) in [
+            (CpuMode::User, 0x0300_7F00),
+            (CpuMode::Irq, 0x0300_7FA0),
+            (CpuMode::Supervisor, 0x0300_7FE0),
+        ] {
+            let mut cpsr = cpu.cpsr();
+            cpsr.set_mode(mode);
+            cpu.set_cpsr(cpsr);
+            assert_eq!(cpu.reg(13), sp, "{:?} SP", mode);
+        }
+    }
+
+    #[test]
+    fn run_until_breakpoint() {
+        let (mut cpu, mut mem) = countdown();
+
+        assert!(cpu.run_until(&mut mem, 100, |cpu| cpu.pc() == 0x0800_0006));
+        assert_eq!(cpu.reg(0), 0);
+        assert_eq!(cpu.reg(1), 0);
+    }
+
+    #[test]
+    fn run_until_budget() {
+        let (mut cpu, mut mem) = countdown();
+
+        // MOV and SUB take a cycle each, the taken BNE overruns the budget.
+        assert!(!cpu.run_until(&mut mem, 4, |cpu| cpu.pc() == 0x0800_0006));
+        assert_eq!(cpu.reg(0), 2);
+        assert_eq!(cpu.cycles(), 5);
+    }
+
+    #[test]
+    fn state_consistent() {
+        let (cpu, _) = countdown();
+        cpu.assert_state_consistent();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "misaligned")]
+    fn state_desync() {
+        let (mut cpu, _) = countdown();
+        cpu.set_pc(0x0800_0002);
+
+        // Clearing the T bit without realigning PC desyncs the state.
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        cpu.assert_state_consistent();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "CPSR state differs from fetch state")]
+    fn msr_state_desync() {
+        let mut mem = FlatMemory::new();
+        // MSR cpsr_c, #0x3F
+        load_into(&mut mem, 0x0800_0000, &arm_program(&[0xE321_F03F]));
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        cpu.set_pc(0x0800_0000);
+        cpu.step(&mut mem);
+        cpu.assert_state_consistent();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "THUMB instruction executed in wrong state")]
+    fn step_after_state_desync() {
+        let mut mem = FlatMemory::new();
+        load_into(
+            &mut mem,
+            0x0800_0000,
+            &arm_program(&[
+                0xE321_F03F, // MSR cpsr_c, #0x3F
+                0xE3A0_0001, // MOV r0, #1
+            ]),
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        cpu.set_pc(0x0800_0000);
+        cpu.step(&mut mem);
+
+        // The MOV is fetched as an ARM word, but decoded as THUMB.
+        cpu.step(&mut mem);
+    }
+
+    #[test]
+    fn step_n() {
+        let (mut cpu, mut mem) = countdown();
+
+        cpu.step_n(&mut mem, 3);
+        assert_eq!(cpu.pc(), 0x0800_0002);
+        assert_eq!(cpu.reg(0), 2);
+    }
+
+    #[test]
+    fn fetch_thumb() {
+        let (mut cpu, mem) = countdown();
+
+        assert_eq!(cpu.fetch(&mem), 0x2003);
+        assert_eq!(cpu.pc(), 0x0800_0002);
+        assert_eq!(cpu.fetch(&mem), 0x3801);
+        assert_eq!(cpu.pc(), 0x0800_0004);
+    }
+
+    #[test]
+    fn branch_flushes_pipeline() {
+        let (mut cpu, mut mem) = countdown();
+
+        // MOV and SUB take 1S each, the taken BNE takes 2S+1N.
+        cpu.step_n(&mut mem, 3);
+        assert_eq!(cpu.cycles(), 5);
+        assert_eq!(cpu.pc(), 0x0800_0002);
+        // The pipeline is refilled from the branch target, two instructions
+        // ahead of the SUB at the target.
+        assert_eq!(cpu.prefetch(), 0x2101);
+
+        // SUB is fetched from the branch target.
+        cpu.step(&mut mem);
+        assert_eq!(cpu.cycles(), 6);
+        assert_eq!(cpu.reg(0), 1);
+        assert_eq!(cpu.prefetch(), 0x2101);
+    }
+
+    #[test]
+    fn mode_transitions() {
+        let mut cpu = Cpu::new();
+        assert_eq!(cpu.suspicious_transition(CpuMode::System), None);
+        cpu.set_mode_checked(CpuMode::System);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::System);
+        assert_eq!(cpu.suspicious_transition(CpuMode::Fiq), None);
+
+        cpu.set_cpsr(Psr::from_raw(0x0000_001F));
+        assert!(cpu.suspicious_transition(CpuMode::Fiq).is_some());
+        assert!(cpu.suspicious_transition(CpuMode::Irq).is_some());
+
+        cpu.set_mode_checked(CpuMode::User);
+        assert!(cpu.suspicious_transition(CpuMode::User).is_none());
+        assert!(cpu.suspicious_transition(CpuMode::Supervisor).is_some());
+    }
+
+    #[test]
+    fn mode_transition_warnings() {
+        let mut cpu = Cpu::new();
+        let logs = capture_logs(|| cpu.set_mode_checked(CpuMode::System));
+        assert!(logs.is_empty(), "{:?}", logs);
+
+        cpu.set_mode_checked(CpuMode::User);
+        let logs = capture_logs(|| cpu.set_mode_checked(CpuMode::Supervisor));
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Supervisor);
+        assert_eq!(logs.len(), 1, "{:?}", logs);
+        assert_eq!(logs[0].0, log::Level::Warn);
+        assert!(logs[0].1.contains("from user mode"), "{}", logs[0].1);
+    }
+
+    #[test]
+    fn state_from_cpsr() {
+        let mut via_cpu = Cpu::new();
+        via_cpu.set_state(CpuState::Thumb);
+
+        let mut via_psr = Cpu::new();
+        let mut cpsr = via_psr.cpsr();
+        cpsr.set_state(CpuState::Thumb);
+        via_psr.set_cpsr(cpsr);
+
+        assert_eq!(via_cpu.state(), CpuState::Thumb);
+        assert_eq!(via_psr.state(), CpuState::Thumb);
+        assert_eq!(via_cpu.cpsr(), via_psr.cpsr());
+
+        via_cpu.set_state(CpuState::Arm);
+        assert_eq!(via_cpu.cpsr().state(), CpuState::Arm);
+        assert_eq!(via_cpu.cpsr(), Cpu::new().cpsr());
+    }
+
+    #[test]
+    fn fetch_arm() {
+        let mut mem = FlatMemory::new();
+        load_into(
+            &mut mem,
+            0x0800_0000,
+            &arm_program(&[0xE3A0_0003, 0xE250_0001]),
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x0800_0000);
+        assert_eq!(cpu.fetch(&mem), 0xE3A0_0003);
+        assert_eq!(cpu.pc(), 0x0800_0004);
+        assert_eq!(cpu.fetch(&mem), 0xE250_0001);
+        assert_eq!(cpu.pc(), 0x0800_0008);
+    }
+}
@@ -0,0 +1,603 @@
+//! THUMB instruction executor.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#thumbinstructionsummary>
+
+use crate::arm::{AddressingMode, DataOpcode};
+use crate::cpu::{alu, load16, load32, load_signed16, load_signed8, store16, store32};
+use crate::cpu::{Cpu, Exception};
+use crate::thumb::{AddSubOperand, AluOp, HiRegisterOp, ImmediateOp, Instruction, SignedOp};
+use crate::{Cond, Memory, ShiftType};
+
+impl Cpu {
+    /// Executes a decoded THUMB instruction.
+    pub(crate) fn execute_thumb(&mut self, mem: &mut impl Memory, instruction: Instruction) {
+        debug_assert_eq!(
+            instruction.executes_in_state(),
+            self.fetch_state,
+            "THUMB instruction executed in wrong state",
+        );
+
+        match instruction {
+            Instruction::MoveShifted { op, offset, rs, rd } => {
+                let (result, carry) = op.apply(self.reg(rs), offset.into(), self.cpsr.C());
+                self.set_nz(result);
+                self.cpsr.set_C(carry);
+                self.set_reg(rd, result);
+            }
+            Instruction::AddSubtract {
+                subtract,
+                operand,
+                rs,
+                rd,
+            } => {
+                let (a, b) = match operand {
+                    AddSubOperand::Register(rn) => (self.reg(rs), self.reg(rn)),
+                    AddSubOperand::Immediate(imm) => (self.reg(rs), imm.into()),
+                };
+                let result = if subtract {
+                    alu::sub(a, b, true)
+                } else {
+                    alu::add(a, b, false)
+                };
+                self.set_nzcv(result);
+                self.set_reg(rd, result.0);
+            }
+            Instruction::Immediate { op, rd, offset } => self.immediate(op, rd, offset),
+            Instruction::Alu { op, rs, rd } => self.alu(op, rs, rd),
+            Instruction::HiRegister { op, rs, rd } => {
+                let (a, b) = (self.operand(rd), self.operand(rs));
+                match op {
+                    HiRegisterOp::Add => self.write_reg(mem, rd, a.wrapping_add(b)),
+                    HiRegisterOp::Cmp => self.set_nzcv(alu::sub(a, b, true)),
+                    HiRegisterOp::Mov => self.write_reg(mem, rd, b),
+                }
+            }
+            Instruction::BranchExchange { rs } => self.branch_exchange(mem, self.operand(rs)),
+            Instruction::PcRelativeLoad { rd, offset } => {
+                let addr = (self.operand(15) & !0b11).wrapping_add(u32::from(offset) * 4);
+                self.set_reg(rd, load32(mem, addr));
+            }
+            Instruction::LoadStoreRegister {
+                load,
+                byte,
+                ro,
+                rb,
+                rd,
+            } => {
+                let addr = self.reg(rb).wrapping_add(self.reg(ro));
+                match (load, byte) {
+                    (true, true) => self.set_reg(rd, mem.read8(addr).into()),
+                    (true, false) => self.set_reg(rd, load32(mem, addr)),
+                    (false, true) => mem.write8(addr, self.reg(rd) as u8),
+                    (false, false) => store32(mem, addr, self.reg(rd)),
+                }
+            }
+            Instruction::LoadStoreSigned { op, ro, rb, rd } => {
+                let addr = self.reg(rb).wrapping_add(self.reg(ro));
+                match op {
+                    SignedOp::Strh => store16(mem, addr, self.reg(rd)),
+                    SignedOp::Ldsb => self.set_reg(rd, load_signed8(mem, addr)),
+                    SignedOp::Ldrh => self.set_reg(rd, load16(mem, addr)),
+                    SignedOp::Ldsh => self.set_reg(rd, load_signed16(mem, addr)),
+                }
+            }
+            Instruction::LoadStoreImmediate {
+                load,
+                byte,
+                offset,
+                rb,
+                rd,
+            } => {
+                let scale = if byte { 1 } else { 4 };
+                let addr = self.reg(rb).wrapping_add(u32::from(offset) * scale);
+                match (load, byte) {
+                    (true, true) => self.set_reg(rd, mem.read8(addr).into()),
+                    (true, false) => self.set_reg(rd, load32(mem, addr)),
+                    (false, true) => mem.write8(addr, self.reg(rd) as u8),
+                    (false, false) => store32(mem, addr, self.reg(rd)),
+                }
+            }
+            Instruction::LoadStoreHalfword {
+                load,
+                offset,
+                rb,
+                rd,
+            } => {
+                let addr = self.reg(rb).wrapping_add(u32::from(offset) * 2);
+                if load {
+                    self.set_reg(rd, load16(mem, addr));
+                } else {
+                    store16(mem, addr, self.reg(rd));
+                }
+            }
+            Instruction::SpRelative { load, rd, offset } => {
+                let addr = self.reg(13).wrapping_add(u32::from(offset) * 4);
+                if load {
+                    self.set_reg(rd, load32(mem, addr));
+                } else {
+                    store32(mem, addr, self.reg(rd));
+                }
+            }
+            Instruction::LoadAddress { sp, rd, offset } => {
+                let base = if sp {
+                    self.reg(13)
+                } else {
+                    self.operand(15) & !0b11
+                };
+                self.set_reg(rd, base.wrapping_add(u32::from(offset) * 4));
+            }
+            Instruction::AddSp { negative, offset } => {
+                let offset = u32::from(offset) * 4;
+                let sp = if negative {
+                    self.reg(13).wrapping_sub(offset)
+                } else {
+                    self.reg(13).wrapping_add(offset)
+                };
+                self.set_reg(13, sp);
+            }
+            Instruction::PushPop {
+                pop,
+                pc_lr,
+                registers,
+            } => {
+                let mut registers = u16::from(registers);
+                if pop {
+                    // On ARMv4, `POP {pc}` ignores bit 0 and stays in THUMB
+                    // state.
+                    registers |= u16::from(pc_lr) << 15;
+                    self.block_transfer(mem, AddressingMode::IA, true, true, 13, registers, false);
+                } else {
+                    registers |= u16::from(pc_lr) << 14;
+                    self.block_transfer(mem, AddressingMode::DB, true, false, 13, registers, false);
+                }
+            }
+            Instruction::MultipleLoadStore {
+                load,
+                rb,
+                registers,
+            } => {
+                let registers = registers.into();
+                self.block_transfer(mem, AddressingMode::IA, true, load, rb, registers, false);
+            }
+            Instruction::ConditionalBranch { cond, offset } => {
+                self.conditional_branch(mem, cond, offset)
+            }
+            Instruction::SoftwareInterrupt { comment } => {
+                self.software_interrupt(mem, comment.into())
+            }
+            Instruction::Branch { offset } => {
+                let offset = i32::from(offset) * 2;
+                self.write_reg(mem, 15, self.operand(15).wrapping_add(offset as u32));
+            }
+            Instruction::BranchLinkPrefix { offset } => {
+                let offset = i32::from(offset) << 12;
+                self.set_reg(14, self.operand(15).wrapping_add(offset as u32));
+            }
+            Instruction::BranchLinkSuffix { offset } => {
+                let target = self.reg(14).wrapping_add(u32::from(offset) * 2);
+                self.set_reg(14, self.pc() | 1);
+                self.write_reg(mem, 15, target & !0b1);
+            }
+            Instruction::Undefined => self.enter_exception(mem, Exception::Undefined),
+        }
+    }
+
+    /// Moves, compares, adds or subtracts an 8-bit immediate, setting flags.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbopcodesregisteroperationsaluhireg>
+    fn immediate(&mut self, op: ImmediateOp, rd: u8, offset: u8) {
+        let (a, b) = (self.reg(rd), u32::from(offset));
+        match op {
+            ImmediateOp::Mov => {
+                self.set_nz(b);
+                self.set_reg(rd, b);
+            }
+            ImmediateOp::Cmp => self.set_nzcv(alu::sub(a, b, true)),
+            ImmediateOp::Add => {
+                let result = alu::add(a, b, false);
+                self.set_nzcv(result);
+                self.set_reg(rd, result.0);
+            }
+            ImmediateOp::Sub => {
+                let result = alu::sub(a, b, true);
+                self.set_nzcv(result);
+                self.set_reg(rd, result.0);
+            }
+        }
+    }
+
+    /// Performs an ALU operation between two low registers.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbopcodesregisteroperationsaluhireg>
+    fn alu(&mut self, op: AluOp, rs: u8, rd: u8) {
+        let (a, b) = (self.reg(rd), self.reg(rs));
+        // Logical operations leave C unchanged.
+        let carry = None;
+
+        let result = match op {
+            AluOp::And => self.alu_op(DataOpcode::And, a, b, carry, true),
+            AluOp::Eor => self.alu_op(DataOpcode::Eor, a, b, carry, true),
+            AluOp::Lsl => self.shift(ShiftType::Lsl, a, b),
+            AluOp::Lsr => self.shift(ShiftType::Lsr, a, b),
+            AluOp::Asr => self.shift(ShiftType::Asr, a, b),
+            AluOp::Adc => self.alu_op(DataOpcode::Adc, a, b, carry, true),
+            AluOp::Sbc => self.alu_op(DataOpcode::Sbc, a, b, carry, true),
+            AluOp::Ror => self.shift(ShiftType::Ror, a, b),
+            AluOp::Tst => self.alu_op(DataOpcode::Tst, a, b, carry, true),
+            AluOp::Neg => self.alu_op(DataOpcode::Rsb, b, 0, carry, true),
+            AluOp::Cmp => self.alu_op(DataOpcode::Cmp, a, b, carry, true),
+            AluOp::Cmn => self.alu_op(DataOpcode::Cmn, a, b, carry, true),
+            AluOp::Orr => self.alu_op(DataOpcode::Orr, a, b, carry, true),
+            // The ARM7TDMI leaves C in a meaningless state after a multiply,
+            // which is modelled by leaving C and V unchanged.
+            AluOp::Mul => {
+                let result = a.wrapping_mul(b);
+                self.set_nz(result);
+                Some(result)
+            }
+            AluOp::Bic => self.alu_op(DataOpcode::Bic, a, b, carry, true),
+            AluOp::Mvn => self.alu_op(DataOpcode::Mvn, a, b, carry, true),
+        };
+
+        if let Some(result) = result {
+            self.set_reg(rd, result);
+        }
+    }
+
+    /// Shifts `value` by the bottom byte of `amount`, setting flags.
+    fn shift(&mut self, shift: ShiftType, value: u32, amount: u32) -> Option<u32> {
+        let (result, carry) = shift.apply(value, amount & 0xFF, self.cpsr.C());
+        self.set_nz(result);
+        self.cpsr.set_C(carry);
+        Some(result)
+    }
+
+    /// Branches by a signed halfword offset relative to `PC+4`, if `cond` is
+    /// met.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbopcodesjumpsandcalls>
+    fn conditional_branch(&mut self, mem: &impl Memory, cond: Cond, offset: i8) {
+        if cond.matches(self.cpsr) {
+            let offset = i32::from(offset) * 2;
+            self.write_reg(mem, 15, self.operand(15).wrapping_add(offset as u32));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psr::Psr;
+    use crate::test_util::{load_into, thumb_program, FlatMemory};
+    use crate::{CpuMode, CpuState};
+
+    /// Runs a single THUMB `opcode` at `addr` with the given CPSR.
+    fn run(cpsr: u32, addr: u32, opcode: u16) -> Cpu {
+        let mut mem = FlatMemory::new();
+        mem.write16(addr, opcode);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(cpsr));
+        cpu.set_pc(addr);
+        cpu.step(&mut mem);
+        cpu
+    }
+
+    /// Runs an add/subtract `opcode` with `r1` and `r2` set, writing to `r0`.
+    fn add_sub(opcode: u16, r1: u32, r2: u32) -> Cpu {
+        let mut mem = FlatMemory::new();
+        mem.write16(0x0800_0000, opcode);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(1, r1);
+        cpu.set_reg(2, r2);
+        cpu.step(&mut mem);
+        cpu
+    }
+
+    #[test]
+    fn add_register_carry() {
+        // ADD r0, r1, r2
+        let cpu = add_sub(0x1888, 0xFFFF_FFFF, 2);
+        assert_eq!(cpu.reg(0), 1);
+        let psr = cpu.cpsr();
+        assert!(!psr.N() && !psr.Z() && psr.C() && !psr.V());
+
+        let cpu = add_sub(0x1888, 0x7FFF_FFFF, 1);
+        assert_eq!(cpu.reg(0), 0x8000_0000);
+        let psr = cpu.cpsr();
+        assert!(psr.N() && !psr.Z() && !psr.C() && psr.V());
+    }
+
+    #[test]
+    fn sub_immediate_borrow() {
+        // SUB r0, r1, #1
+        let cpu = add_sub(0x1E48, 0, 0);
+        assert_eq!(cpu.reg(0), 0xFFFF_FFFF);
+        let psr = cpu.cpsr();
+        assert!(psr.N() && !psr.Z() && !psr.C() && !psr.V());
+
+        let cpu = add_sub(0x1E48, 2, 0);
+        assert_eq!(cpu.reg(0), 1);
+        assert!(cpu.cpsr().C());
+    }
+
+    #[test]
+    fn sub_register_zero() {
+        // SUB r0, r1, r2
+        let cpu = add_sub(0x1A88, 0x1234, 0x1234);
+        assert_eq!(cpu.reg(0), 0);
+        let psr = cpu.cpsr();
+        assert!(!psr.N() && psr.Z() && psr.C() && !psr.V());
+
+        // ADD r0, r1, #0
+        let cpu = add_sub(0x1C08, 0, 0);
+        assert!(cpu.cpsr().Z());
+        assert!(!cpu.cpsr().C());
+    }
+
+    #[test]
+    fn alu_ops() {
+        // (op, r0, r1, NZCV in, r0 out, NZCV out)
+        #[rustfmt::skip]
+        let cases: &[(u16, u32, u32, u32, u32, u32)] = &[
+            // AND keeps C and V.
+            (0x0, 0xF0F0_0000, 0xFF00_0000, 0b0011, 0xF000_0000, 0b1011),
+            // EOR keeps C and V.
+            (0x1, 0x1234, 0x1234, 0b0011, 0, 0b0111),
+            // LSL by the bottom byte of r1 (32), C is bit 0.
+            (0x2, 1, 0x120, 0b0001, 0, 0b0111),
+            // LSR by 0 leaves the value and C unchanged.
+            (0x3, 0x8000_0000, 0, 0b0010, 0x8000_0000, 0b1010),
+            // ASR by more than 32 fills with the sign bit.
+            (0x4, 0x8000_0000, 40, 0b0000, 0xFFFF_FFFF, 0b1010),
+            // ADC adds the carry in.
+            (0x5, 0xFFFF_FFFF, 0, 0b0010, 0, 0b0110),
+            // SBC subtracts the inverted carry in.
+            (0x6, 5, 5, 0b0000, 0xFFFF_FFFF, 0b1000),
+            // ROR by 32 leaves the value unchanged, C is bit 31.
+            (0x7, 1, 32, 0b0010, 1, 0b0000),
+            // TST only sets flags.
+            (0x8, 0xF0, 0x0F, 0b0011, 0xF0, 0b0111),
+            // NEG is `0 - r1`, ignoring r0.
+            (0x9, 0x99, 0, 0b0000, 0, 0b0110),
+            (0x9, 0x99, 0x8000_0000, 0b0010, 0x8000_0000, 0b1001),
+            // CMP only sets flags, C is clear on borrow.
+            (0xA, 1, 2, 0b0010, 1, 0b1000),
+            // CMN only sets flags.
+            (0xB, 0x7FFF_FFFF, 1, 0b0000, 0x7FFF_FFFF, 0b1001),
+            // ORR keeps C and V.
+            (0xC, 0, 0, 0b0011, 0, 0b0111),
+            // MUL leaves C and V unchanged.
+            (0xD, 0x1_0000, 0x1_0000, 0b0011, 0, 0b0111),
+            // BIC keeps C and V.
+            (0xE, 0xFFFF_FFFF, 0x7FFF_FFFF, 0b0001, 0x8000_0000, 0b1001),
+            // MVN ignores r0.
+            (0xF, 0x99, 0xFFFF_FFFF, 0b0000, 0, 0b0100),
+        ];
+
+        for &(op, r0, r1, flags, result, expected) in cases {
+            // <op> r0, r1
+            let opcode = 0x4008 | (op << 6);
+            let mut mem = FlatMemory::new();
+            mem.write16(0x0800_0000, opcode);
+
+            let mut cpu = Cpu::new();
+            cpu.set_cpsr(Psr::from_raw((flags << 28) | 0x3F));
+            cpu.set_pc(0x0800_0000);
+            cpu.set_reg(0, r0);
+            cpu.set_reg(1, r1);
+            cpu.step(&mut mem);
+
+            let name = crate::thumb::disassemble(opcode);
+            assert_eq!(cpu.reg(0), result, "{}", name);
+            assert_eq!(cpu.cpsr().raw() >> 28, expected, "{} flags", name);
+        }
+    }
+
+    #[test]
+    fn swi() {
+        // SWI #0xAB
+        let cpu = run(0x6000_003F, 0x0800_0100, 0xDFAB);
+
+        assert_eq!(cpu.pc(), 0x0000_0008);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Supervisor);
+        assert_eq!(cpu.cpsr().state(), CpuState::Arm);
+        assert!(cpu.cpsr().irq_disabled());
+        assert_eq!(cpu.reg(14), 0x0800_0102);
+        assert_eq!(cpu.spsr(), Some(Psr::from_raw(0x6000_003F)));
+    }
+
+    #[test]
+    fn swi_hook() {
+        let mut mem = FlatMemory::new();
+        load_into(
+            &mut mem,
+            0x0800_0100,
+            &thumb_program(&[
+                0xDFAB, // SWI #0xAB
+                0xDF01, // SWI #0x01
+            ]),
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0100);
+        // Handle the interrupt, recording the comment and return address.
+        cpu.set_swi_hook(Some(|cpu, comment| {
+            cpu.set_reg(0, comment);
+            cpu.set_reg(1, cpu.pc());
+            true
+        }));
+
+        cpu.step(&mut mem);
+        assert_eq!(cpu.reg(0), 0xAB);
+        assert_eq!(cpu.reg(1), 0x0800_0102);
+        assert_eq!(cpu.pc(), 0x0800_0102);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::System);
+
+        cpu.set_swi_hook(None);
+        cpu.step(&mut mem);
+        assert_eq!(cpu.reg(0), 0xAB);
+        assert_eq!(cpu.pc(), 0x0000_0008);
+        assert_eq!(cpu.cpsr().mode(), CpuMode::Supervisor);
+    }
+
+    #[test]
+    fn mul_zero() {
+        let mut mem = FlatMemory::new();
+        // MUL r0, r1
+        mem.write16(0x0800_0000, 0x4348);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0xB000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(0, 0x1234_5678);
+        cpu.set_reg(1, 0);
+        cpu.step(&mut mem);
+
+        assert_eq!(cpu.reg(0), 0);
+        assert!(cpu.cpsr().Z());
+        assert!(!cpu.cpsr().N());
+        assert!(cpu.cpsr().C());
+        assert!(cpu.cpsr().V());
+    }
+
+    #[test]
+    fn mul_negative() {
+        let mut mem = FlatMemory::new();
+        // MUL r0, r1
+        mem.write16(0x0800_0000, 0x4348);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x4000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(0, 3);
+        cpu.set_reg(1, -5i32 as u32);
+        cpu.step(&mut mem);
+
+        assert_eq!(cpu.reg(0), -15i32 as u32);
+        assert!(cpu.cpsr().N());
+        assert!(!cpu.cpsr().Z());
+    }
+
+    #[test]
+    fn conditional_branch_taken_backward() {
+        // BNE #-0x8, Z clear.
+        let cpu = run(0x0000_003F, 0x0800_0010, 0xD1FA);
+        assert_eq!(cpu.pc(), 0x0800_0008);
+    }
+
+    #[test]
+    fn conditional_branch_not_taken() {
+        // BEQ #+0x10, Z clear.
+        let cpu = run(0x0000_003F, 0x0800_0010, 0xD006);
+        assert_eq!(cpu.pc(), 0x0800_0012);
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut mem = FlatMemory::new();
+        let program: [u16; 4] = [
+            0xB530, // PUSH {r4, r5, lr}
+            0x2400, // MOV r4, #0x0
+            0x2500, // MOV r5, #0x0
+            0xBD30, // POP {r4, r5, pc}
+        ];
+        load_into(&mut mem, 0x0800_0000, &thumb_program(&program));
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(4, 0x1111_1111);
+        cpu.set_reg(5, 0x2222_2222);
+        cpu.set_reg(13, 0x0300_7F00);
+        cpu.set_reg(14, 0x0800_0101);
+
+        cpu.step(&mut mem);
+        assert_eq!(cpu.reg(13), 0x0300_7EF4);
+        assert_eq!(mem.read32(0x0300_7EF4), 0x1111_1111);
+        assert_eq!(mem.read32(0x0300_7EF8), 0x2222_2222);
+        assert_eq!(mem.read32(0x0300_7EFC), 0x0800_0101);
+
+        cpu.step_n(&mut mem, 3);
+        assert_eq!(cpu.reg(4), 0x1111_1111);
+        assert_eq!(cpu.reg(5), 0x2222_2222);
+        assert_eq!(cpu.reg(13), 0x0300_7F00);
+        assert_eq!(cpu.pc(), 0x0800_0100);
+        assert_eq!(cpu.cpsr().state(), CpuState::Thumb);
+    }
+
+    #[test]
+    fn pop_pc_stays_thumb() {
+        let mut mem = FlatMemory::new();
+        // POP {pc}
+        mem.write16(0x0800_0000, 0xBD00);
+        // An even address, which would switch to ARM state on ARMv5.
+        mem.write32(0x0300_7F00, 0x0800_0200);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(13, 0x0300_7F00);
+        cpu.step(&mut mem);
+
+        assert_eq!(cpu.pc(), 0x0800_0200);
+        assert_eq!(cpu.reg(13), 0x0300_7F04);
+        assert_eq!(cpu.cpsr().state(), CpuState::Thumb);
+    }
+
+    /// Runs a single THUMB `opcode` at `0x08000000` in `mem`, with `r1` set
+    /// to `0x02000000`.
+    fn run_transfer(mem: &mut FlatMemory, opcode: u16) -> Cpu {
+        mem.write16(0x0800_0000, opcode);
+
+        let mut cpu = Cpu::new();
+        cpu.set_cpsr(Psr::from_raw(0x0000_003F));
+        cpu.set_pc(0x0800_0000);
+        cpu.set_reg(0, 0x8899_AABB);
+        cpu.set_reg(1, 0x0200_0000);
+        cpu.step(mem);
+        cpu
+    }
+
+    #[test]
+    fn str_immediate_offset() {
+        let mut mem = FlatMemory::new();
+
+        // STR r0, [r1, #0x1C]
+        run_transfer(&mut mem, 0x61C8);
+        assert_eq!(mem.read32(0x0200_001C), 0x8899_AABB);
+        assert_eq!(mem.read32(0x0200_0018), 0);
+    }
+
+    #[test]
+    fn ldrb_immediate_offset() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0004, 0x1234_F678);
+
+        // LDRB r0, [r1, #0x5]
+        let cpu = run_transfer(&mut mem, 0x7948);
+        assert_eq!(cpu.reg(0), 0x0000_00F6);
+    }
+
+    #[test]
+    fn ldrh_immediate_offset() {
+        let mut mem = FlatMemory::new();
+        mem.write32(0x0200_0010, 0x1234_F678);
+
+        // LDRH r0, [r1, #0x10]
+        let cpu = run_transfer(&mut mem, 0x8A08);
+        assert_eq!(cpu.reg(0), 0x0000_F678);
+    }
+}
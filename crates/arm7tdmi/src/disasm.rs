@@ -0,0 +1,122 @@
+//! Formatting helpers shared by the ARM and THUMB disassemblers.
+
+use std::fmt;
+
+use crate::Cond;
+
+/// A register operand.
+#[derive(Clone, Copy)]
+pub(crate) struct Reg(pub u8);
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            13 => f.write_str("sp"),
+            14 => f.write_str("lr"),
+            15 => f.write_str("pc"),
+            n => write!(f, "r{}", n),
+        }
+    }
+}
+
+/// A register list operand, bit `n` is set if `Rn` is in the list.
+#[derive(Clone, Copy)]
+pub(crate) struct RegList(pub u16);
+
+impl fmt::Display for RegList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("{")?;
+
+        let mut first = true;
+        for n in (0..16).filter(|n| self.0 & (1 << n) != 0) {
+            if !first {
+                f.write_str(", ")?;
+            }
+            first = false;
+
+            write!(f, "{}", Reg(n))?;
+        }
+
+        f.write_str("}")
+    }
+}
+
+/// An unsigned immediate operand.
+#[derive(Clone, Copy)]
+pub(crate) struct Imm(pub u32);
+
+impl fmt::Display for Imm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:#X}", self.0)
+    }
+}
+
+/// A signed immediate offset, with the sign written only if negative.
+#[derive(Clone, Copy)]
+pub(crate) struct Offset(pub i32);
+
+impl fmt::Display for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "#-{:#X}", self.0.unsigned_abs())
+        } else {
+            write!(f, "#{:#X}", self.0)
+        }
+    }
+}
+
+/// A branch target relative to the address of the branch instruction.
+#[derive(Clone, Copy)]
+pub(crate) struct Relative(pub i32);
+
+impl fmt::Display for Relative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "#-{:#X}", self.0.unsigned_abs())
+        } else {
+            write!(f, "#+{:#X}", self.0)
+        }
+    }
+}
+
+/// An absolute address.
+#[derive(Clone, Copy)]
+pub(crate) struct Address(pub u32);
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#0x{:08X}", self.0)
+    }
+}
+
+/// A branch target, resolved to an absolute address if the address of the
+/// branch instruction is known.
+#[derive(Clone, Copy)]
+pub(crate) struct Target {
+    /// The offset of the target from the branch instruction.
+    pub offset: i32,
+    /// The address of the branch instruction.
+    pub address: Option<u32>,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            Some(address) => Address(address.wrapping_add(self.offset as u32)).fmt(f),
+            None => Relative(self.offset).fmt(f),
+        }
+    }
+}
+
+/// A condition mnemonic suffix, which is empty for `AL`.
+#[derive(Clone, Copy)]
+pub(crate) struct CondSuffix(pub Cond);
+
+impl fmt::Display for CondSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Cond::AL => Ok(()),
+            cond => cond.fmt(f),
+        }
+    }
+}
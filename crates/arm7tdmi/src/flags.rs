@@ -0,0 +1,57 @@
+//! Condition flag sets.
+
+use std::ops::BitOr;
+
+/// A set of the condition flags (N, Z, C, V) in a PSR.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armcpuflagsconditionfieldcond>
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FlagSet(u8);
+
+impl FlagSet {
+    /// No flags.
+    pub const NONE: FlagSet = FlagSet(0);
+    /// Overflow flag.
+    pub const V: FlagSet = FlagSet(1 << 0);
+    /// Carry flag.
+    pub const C: FlagSet = FlagSet(1 << 1);
+    /// Zero flag.
+    pub const Z: FlagSet = FlagSet(1 << 2);
+    /// Sign flag.
+    pub const N: FlagSet = FlagSet(1 << 3);
+    /// Sign and zero flags, set by logical operations without a shifter carry.
+    pub const NZ: FlagSet = FlagSet::N.union(FlagSet::Z);
+    /// Sign, zero and carry flags, set by logical operations and shifts.
+    pub const NZC: FlagSet = FlagSet::NZ.union(FlagSet::C);
+    /// All flags, set by arithmetic operations.
+    pub const NZCV: FlagSet = FlagSet::NZC.union(FlagSet::V);
+
+    /// Returns the union of two sets.
+    #[inline]
+    pub const fn union(self, other: FlagSet) -> FlagSet {
+        FlagSet(self.0 | other.0)
+    }
+
+    /// Checks if all flags in `other` are in the set.
+    #[inline]
+    pub const fn contains(self, other: FlagSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks if the set is empty.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for FlagSet {
+    type Output = FlagSet;
+
+    #[inline]
+    fn bitor(self, rhs: FlagSet) -> FlagSet {
+        self.union(rhs)
+    }
+}
@@ -0,0 +1,134 @@
+//! Instructions decoded from either instruction set.
+
+use crate::{arm, thumb, CpuState};
+
+/// A decoded instruction from either the ARM or THUMB instruction set, along
+/// with the address and opcode it was decoded from.
+///
+/// This provides a single instruction type for tracing mixed ARM and THUMB
+/// execution.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodedInstruction {
+    /// An ARM instruction.
+    Arm {
+        /// Address of the instruction.
+        address: u32,
+        /// Raw opcode.
+        opcode: u32,
+        instruction: arm::Instruction,
+    },
+    /// A THUMB instruction.
+    Thumb {
+        /// Address of the instruction.
+        address: u32,
+        /// Raw opcode.
+        opcode: u16,
+        instruction: thumb::Instruction,
+    },
+}
+
+impl DecodedInstruction {
+    /// Decodes an ARM opcode located at `address`.
+    pub fn arm(address: u32, opcode: u32) -> DecodedInstruction {
+        DecodedInstruction::Arm {
+            address,
+            opcode,
+            instruction: arm::decode(opcode),
+        }
+    }
+
+    /// Decodes a THUMB opcode located at `address`.
+    pub fn thumb(address: u32, opcode: u16) -> DecodedInstruction {
+        DecodedInstruction::Thumb {
+            address,
+            opcode,
+            instruction: thumb::decode(opcode),
+        }
+    }
+
+    /// Returns the address of the instruction.
+    pub fn address(&self) -> u32 {
+        match *self {
+            DecodedInstruction::Arm { address, .. } => address,
+            DecodedInstruction::Thumb { address, .. } => address,
+        }
+    }
+
+    /// Returns the instruction set the instruction was decoded from.
+    pub fn state(&self) -> CpuState {
+        match self {
+            DecodedInstruction::Arm { .. } => CpuState::Arm,
+            DecodedInstruction::Thumb { .. } => CpuState::Thumb,
+        }
+    }
+
+    /// Returns the raw bytes of the instruction, in little-endian order.
+    pub fn bytes(&self) -> Vec<u8> {
+        match *self {
+            DecodedInstruction::Arm { opcode, .. } => opcode.to_le_bytes().to_vec(),
+            DecodedInstruction::Thumb { opcode, .. } => opcode.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Disassembles the instruction, using the disassembler for the
+    /// instruction set it was decoded from.
+    ///
+    /// Branch targets and PC-relative load addresses are resolved using the
+    /// address of the instruction.
+    pub fn disassemble(&self) -> String {
+        match self {
+            DecodedInstruction::Arm {
+                address,
+                instruction,
+                ..
+            } => instruction.display_at(*address).to_string(),
+            DecodedInstruction::Thumb {
+                address,
+                instruction,
+                ..
+            } => instruction.display_at(*address).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disassemble_dispatch() {
+        // ADD r0, r1, r2
+        let arm = DecodedInstruction::arm(0x0800_0000, 0xE081_0002);
+        // ADD r0, r1, r2
+        let thumb = DecodedInstruction::thumb(0x0800_0004, 0x1888);
+
+        assert_eq!(arm.state(), CpuState::Arm);
+        assert_eq!(arm.address(), 0x0800_0000);
+        assert_eq!(arm.bytes(), [0x02, 0x00, 0x81, 0xE0]);
+        assert_eq!(arm.disassemble(), "ADD r0, r1, r2");
+
+        assert_eq!(thumb.state(), CpuState::Thumb);
+        assert_eq!(thumb.address(), 0x0800_0004);
+        assert_eq!(thumb.bytes(), [0x88, 0x18]);
+        assert_eq!(thumb.disassemble(), "ADD r0, r1, r2");
+    }
+
+    #[test]
+    fn disassemble_at_address() {
+        // B #+0x100
+        let arm = DecodedInstruction::arm(0x0800_0000, 0xEA00_003E);
+        // BEQ #+0x4
+        let thumb = DecodedInstruction::thumb(0x0800_0010, 0xD000);
+
+        assert_eq!(
+            arm.disassemble(),
+            arm::disassemble_at(0xEA00_003E, 0x0800_0000)
+        );
+        assert_eq!(
+            thumb.disassemble(),
+            thumb::disassemble_at(0xD000, 0x0800_0010)
+        );
+        assert_eq!(arm.disassemble(), "B #0x08000100");
+        assert_eq!(thumb.disassemble(), "BEQ #0x08000014");
+    }
+}
@@ -9,11 +9,26 @@ use std::fmt;
 use int_enum::IntEnum;
 
 mod bit;
+mod cpu;
+mod disasm;
+mod flags;
+mod instruction;
+mod memory;
 mod psr;
+mod registers;
 
 pub mod arm;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod thumb;
 
+pub use crate::cpu::{Cpu, Exception, SwiHook};
+pub use crate::flags::FlagSet;
+pub use crate::instruction::DecodedInstruction;
+pub use crate::memory::Memory;
+pub use crate::psr::{Psr, PsrFields};
+pub use crate::registers::Registers;
+
 /// Cpu state.
 ///
 /// # Source
@@ -28,6 +43,23 @@ pub enum CpuState {
     Thumb = 1,
 }
 
+impl CpuState {
+    /// Returns the size of an instruction in bytes.
+    #[inline]
+    pub const fn instruction_size(self) -> u32 {
+        match self {
+            CpuState::Arm => 4,
+            CpuState::Thumb => 2,
+        }
+    }
+
+    /// Returns the mask aligning `PC` to the instruction size.
+    #[inline]
+    pub const fn pc_alignment_mask(self) -> u32 {
+        !(self.instruction_size() - 1)
+    }
+}
+
 impl fmt::Display for CpuState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -61,6 +93,17 @@ pub enum CpuMode {
     System = 0b11111,
 }
 
+impl CpuMode {
+    /// Decodes a mode from the low 5 bits of `bits`, the mode field of a PSR.
+    ///
+    /// Returns the 5-bit field if it is not a valid mode, for use where PSR
+    /// values are untrusted.
+    pub fn from_psr_bits(bits: u8) -> Result<CpuMode, u8> {
+        let bits = bits & 0b11111;
+        CpuMode::try_from(bits).map_err(|_| bits)
+    }
+}
+
 impl fmt::Display for CpuMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -124,3 +167,281 @@ pub enum Cond {
     /// Invalid.
     Invalid = 0xF,
 }
+
+impl Cond {
+    /// Checks if the condition is satisfied by the flags in `psr`.
+    ///
+    /// The `Invalid` condition is never satisfied.
+    pub fn matches(self, psr: Psr) -> bool {
+        self.eval(psr.N(), psr.Z(), psr.C(), psr.V())
+    }
+
+    /// Checks if the condition is satisfied by the N, Z, C and V flags.
+    ///
+    /// The `Invalid` condition is never satisfied.
+    pub fn eval(self, n: bool, z: bool, c: bool, v: bool) -> bool {
+        match self {
+            Cond::EQ => z,
+            Cond::NE => !z,
+            Cond::HS => c,
+            Cond::LO => !c,
+            Cond::MI => n,
+            Cond::PL => !n,
+            Cond::VS => v,
+            Cond::VC => !v,
+            Cond::HI => c && !z,
+            Cond::LS => !c || z,
+            Cond::GE => n == v,
+            Cond::LT => n != v,
+            Cond::GT => !z && n == v,
+            Cond::LE => z || n != v,
+            Cond::AL => true,
+            Cond::Invalid => false,
+        }
+    }
+
+    /// Returns the flags read by the condition.
+    pub fn flags_used(self) -> FlagSet {
+        match self {
+            Cond::EQ | Cond::NE => FlagSet::Z,
+            Cond::HS | Cond::LO => FlagSet::C,
+            Cond::MI | Cond::PL => FlagSet::N,
+            Cond::VS | Cond::VC => FlagSet::V,
+            Cond::HI | Cond::LS => FlagSet::C | FlagSet::Z,
+            Cond::GE | Cond::LT => FlagSet::N | FlagSet::V,
+            Cond::GT | Cond::LE => FlagSet::N | FlagSet::Z | FlagSet::V,
+            Cond::AL | Cond::Invalid => FlagSet::NONE,
+        }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cond::EQ => f.pad("EQ"),
+            Cond::NE => f.pad("NE"),
+            Cond::HS => f.pad("HS"),
+            Cond::LO => f.pad("LO"),
+            Cond::MI => f.pad("MI"),
+            Cond::PL => f.pad("PL"),
+            Cond::VS => f.pad("VS"),
+            Cond::VC => f.pad("VC"),
+            Cond::HI => f.pad("HI"),
+            Cond::LS => f.pad("LS"),
+            Cond::GE => f.pad("GE"),
+            Cond::LT => f.pad("LT"),
+            Cond::GT => f.pad("GT"),
+            Cond::LE => f.pad("LE"),
+            Cond::AL => f.pad("AL"),
+            Cond::Invalid => f.pad("NV"),
+        }
+    }
+}
+
+/// Barrel shifter operations.
+///
+/// Immediate shift amounts are normalised when decoding, such that `LSR #0`
+/// and `ASR #0` are represented as shifts by `32`, and `ROR #0` is represented
+/// as [`Rrx`].
+///
+/// [`Rrx`]: ShiftType::Rrx
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessing>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShiftType {
+    /// Logical shift left.
+    Lsl,
+    /// Logical shift right.
+    Lsr,
+    /// Arithmetic shift right.
+    Asr,
+    /// Rotate right.
+    Ror,
+    /// Rotate right extended (by one bit, through carry).
+    Rrx,
+}
+
+impl ShiftType {
+    /// Shifts `value` by `amount`, returning the result and the shifter carry
+    /// out.
+    ///
+    /// This is the barrel shifter used by both immediate and register
+    /// specified shifts. The amount is as normalised by the decoder for
+    /// immediate shifts, or the bottom byte of the register for register
+    /// specified shifts, such that:
+    ///
+    /// - A shift by `0` leaves `value` unchanged, with the carry out equal to
+    ///   `carry_in`.
+    /// - `LSL` and `LSR` by `32` produce `0`, with the carry out equal to bit
+    ///   `0` and bit `31` respectively; by more than `32` they produce `0` with
+    ///   the carry out cleared.
+    /// - `ASR` by `32` or more fills the result with bit `31`, which is also
+    ///   the carry out.
+    /// - `ROR` by a non-zero multiple of `32` leaves `value` unchanged, with
+    ///   the carry out equal to bit `31`; otherwise it rotates by the amount
+    ///   modulo `32`.
+    /// - `RRX` ignores `amount`, shifting right by one and inserting
+    ///   `carry_in` as bit `31`.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#armopcodesdataprocessing>
+    pub fn apply(self, value: u32, amount: u32, carry_in: bool) -> (u32, bool) {
+        let bit = |n: u32| value & (1 << n) != 0;
+
+        if amount == 0 && self != ShiftType::Rrx {
+            return (value, carry_in);
+        }
+
+        match self {
+            ShiftType::Lsl => match amount {
+                1..=31 => (value << amount, bit(32 - amount)),
+                32 => (0, bit(0)),
+                _ => (0, false),
+            },
+            ShiftType::Lsr => match amount {
+                1..=31 => (value >> amount, bit(amount - 1)),
+                32 => (0, bit(31)),
+                _ => (0, false),
+            },
+            ShiftType::Asr => match amount {
+                1..=31 => (((value as i32) >> amount) as u32, bit(amount - 1)),
+                _ => (((value as i32) >> 31) as u32, bit(31)),
+            },
+            ShiftType::Ror => match amount % 32 {
+                0 => (value, bit(31)),
+                amount => (value.rotate_right(amount), bit(amount - 1)),
+            },
+            ShiftType::Rrx => ((u32::from(carry_in) << 31) | (value >> 1), bit(0)),
+        }
+    }
+}
+
+impl fmt::Display for ShiftType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShiftType::Lsl => f.pad("LSL"),
+            ShiftType::Lsr => f.pad("LSR"),
+            ShiftType::Asr => f.pad("ASR"),
+            ShiftType::Ror => f.pad("ROR"),
+            ShiftType::Rrx => f.pad("RRX"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cond_eval() {
+        for raw in [
+            0x0000_0000,
+            0x4000_0000,
+            0x6000_0000,
+            0x9000_0000,
+            0xF000_0000,
+        ] {
+            let psr = Psr::from_raw(raw);
+            for cond in (0..=0xF).map(|n| Cond::try_from(n).unwrap()) {
+                let (n, z, c, v) = (psr.N(), psr.Z(), psr.C(), psr.V());
+                assert_eq!(
+                    cond.matches(psr),
+                    cond.eval(n, z, c, v),
+                    "{:?} {:#X}",
+                    cond,
+                    raw
+                );
+            }
+        }
+
+        assert!(Cond::HI.eval(false, false, true, false));
+        assert!(!Cond::HI.eval(false, true, true, false));
+        assert!(Cond::GE.eval(true, false, false, true));
+        assert!(!Cond::Invalid.eval(false, false, false, false));
+    }
+
+    #[test]
+    fn cpu_state_alignment() {
+        assert_eq!(CpuState::Arm.instruction_size(), 4);
+        assert_eq!(CpuState::Arm.pc_alignment_mask(), 0xFFFF_FFFC);
+        assert_eq!(CpuState::Thumb.instruction_size(), 2);
+        assert_eq!(CpuState::Thumb.pc_alignment_mask(), 0xFFFF_FFFE);
+    }
+
+    #[test]
+    fn cpu_mode_from_psr_bits() {
+        let modes = [
+            (0b10000, CpuMode::User),
+            (0b10001, CpuMode::Fiq),
+            (0b10010, CpuMode::Irq),
+            (0b10011, CpuMode::Supervisor),
+            (0b10111, CpuMode::Abort),
+            (0b11011, CpuMode::Undefined),
+            (0b11111, CpuMode::System),
+        ];
+        for (bits, mode) in modes {
+            assert_eq!(CpuMode::from_psr_bits(bits), Ok(mode));
+            // Bits above the mode field are ignored.
+            assert_eq!(CpuMode::from_psr_bits(0xE0 | bits), Ok(mode));
+        }
+
+        assert_eq!(CpuMode::from_psr_bits(0b00000), Err(0b00000));
+        assert_eq!(CpuMode::from_psr_bits(0b10100), Err(0b10100));
+        assert_eq!(CpuMode::from_psr_bits(0xF4), Err(0b10100));
+    }
+
+    #[test]
+    fn shift_apply() {
+        const VALUE: u32 = 0x8000_0003;
+
+        #[rustfmt::skip]
+        let cases = [
+            // (shift, amount, carry_in, result, carry_out)
+            (ShiftType::Lsl, 0, true, VALUE, true),
+            (ShiftType::Lsl, 1, false, 0x0000_0006, true),
+            (ShiftType::Lsl, 31, false, 0x8000_0000, true),
+            (ShiftType::Lsl, 32, false, 0, true),
+            (ShiftType::Lsl, 33, true, 0, false),
+            (ShiftType::Lsr, 0, false, VALUE, false),
+            (ShiftType::Lsr, 1, false, 0x4000_0001, true),
+            (ShiftType::Lsr, 31, false, 0x0000_0001, false),
+            (ShiftType::Lsr, 32, false, 0, true),
+            (ShiftType::Lsr, 33, true, 0, false),
+            (ShiftType::Asr, 0, true, VALUE, true),
+            (ShiftType::Asr, 1, false, 0xC000_0001, true),
+            (ShiftType::Asr, 31, false, 0xFFFF_FFFF, false),
+            (ShiftType::Asr, 32, false, 0xFFFF_FFFF, true),
+            (ShiftType::Asr, 33, false, 0xFFFF_FFFF, true),
+            (ShiftType::Ror, 0, false, VALUE, false),
+            (ShiftType::Ror, 1, false, 0xC000_0001, true),
+            (ShiftType::Ror, 31, false, 0x0000_0007, false),
+            (ShiftType::Ror, 32, false, VALUE, true),
+            (ShiftType::Ror, 33, false, 0xC000_0001, true),
+            (ShiftType::Rrx, 0, false, 0x4000_0001, true),
+            (ShiftType::Rrx, 1, true, 0xC000_0001, true),
+            (ShiftType::Rrx, 31, false, 0x4000_0001, true),
+            (ShiftType::Rrx, 32, true, 0xC000_0001, true),
+            (ShiftType::Rrx, 33, false, 0x4000_0001, true),
+        ];
+
+        for (shift, amount, carry_in, result, carry_out) in cases {
+            assert_eq!(
+                shift.apply(VALUE, amount, carry_in),
+                (result, carry_out),
+                "{} #{} (C={})",
+                shift,
+                amount,
+                carry_in,
+            );
+        }
+    }
+
+    #[test]
+    fn shift_apply_positive_asr() {
+        assert_eq!(ShiftType::Asr.apply(0x7FFF_FFFF, 32, true), (0, false));
+        assert_eq!(ShiftType::Asr.apply(0x7FFF_FFFF, 33, true), (0, false));
+    }
+}
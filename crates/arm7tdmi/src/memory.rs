@@ -0,0 +1,34 @@
+/// A memory bus accessed by the CPU.
+///
+/// Values are little-endian. Halfword and word accesses are aligned by the CPU
+/// before being performed.
+pub trait Memory {
+    /// Reads a byte.
+    fn read8(&self, addr: u32) -> u8;
+
+    /// Reads a halfword.
+    fn read16(&self, addr: u32) -> u16 {
+        u16::from_le_bytes([self.read8(addr), self.read8(addr | 1)])
+    }
+
+    /// Reads a word.
+    fn read32(&self, addr: u32) -> u32 {
+        u32::from(self.read16(addr)) | (u32::from(self.read16(addr | 2)) << 16)
+    }
+
+    /// Writes a byte.
+    fn write8(&mut self, addr: u32, value: u8);
+
+    /// Writes a halfword.
+    fn write16(&mut self, addr: u32, value: u16) {
+        let [lo, hi] = value.to_le_bytes();
+        self.write8(addr, lo);
+        self.write8(addr | 1, hi);
+    }
+
+    /// Writes a word.
+    fn write32(&mut self, addr: u32, value: u32) {
+        self.write16(addr, value as u16);
+        self.write16(addr | 2, (value >> 16) as u16);
+    }
+}
@@ -20,21 +20,135 @@ pub struct Psr {
     raw: u32,
 }
 
+/// The fields of a program status register, decoupled from their bit
+/// positions.
+///
+/// Reserved bits (27-8) are not represented.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PsrFields {
+    /// Operating mode (bits 4-0).
+    pub mode: CpuMode,
+    /// State bit (T, bit 5).
+    pub state: CpuState,
+    /// FIQ disable (F, bit 6).
+    pub fiq_disabled: bool,
+    /// IRQ disable (I, bit 7).
+    pub irq_disabled: bool,
+    /// Overflow flag (V, bit 28).
+    pub v: bool,
+    /// Carry flag (C, bit 29).
+    pub c: bool,
+    /// Zero flag (Z, bit 30).
+    pub z: bool,
+    /// Sign flag (N, bit 31).
+    pub n: bool,
+}
+
 impl Psr {
+    /// Creates a PSR from its raw value.
+    #[inline]
+    pub const fn from_raw(raw: u32) -> Psr {
+        Psr { raw }
+    }
+
+    /// Returns the raw value of the PSR.
+    #[inline]
+    pub const fn raw(self) -> u32 {
+        self.raw
+    }
+
+    /// Creates a PSR from its fields.
+    ///
+    /// Reserved bits are zero.
+    pub fn from_fields(fields: PsrFields) -> Psr {
+        let raw = 0u32
+            .set_bits::<0, 5>(u8::from(fields.mode).into())
+            .set_bit::<5>(fields.state.into())
+            .set_bit::<6>(fields.fiq_disabled)
+            .set_bit::<7>(fields.irq_disabled)
+            .set_bit::<28>(fields.v)
+            .set_bit::<29>(fields.c)
+            .set_bit::<30>(fields.z)
+            .set_bit::<31>(fields.n);
+
+        Psr { raw }
+    }
+
+    /// Creates a User mode, ARM state PSR with the given condition flags.
+    ///
+    /// Interrupts are enabled and reserved bits are zero.
+    pub fn with_flags(n: bool, z: bool, c: bool, v: bool) -> Psr {
+        Psr::from_fields(PsrFields {
+            mode: CpuMode::User,
+            state: CpuState::Arm,
+            fiq_disabled: false,
+            irq_disabled: false,
+            v,
+            c,
+            z,
+            n,
+        })
+    }
+
+    /// Returns the fields of the PSR.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mode bits are not a valid [`CpuMode`].
+    pub fn to_fields(self) -> PsrFields {
+        PsrFields {
+            mode: self.mode(),
+            state: self.state(),
+            fiq_disabled: self.fiq_disabled(),
+            irq_disabled: self.irq_disabled(),
+            v: self.V(),
+            c: self.C(),
+            z: self.Z(),
+            n: self.N(),
+        }
+    }
+
+    /// Returns the control bits (bits 7-0): the mode, state and interrupt
+    /// disable bits.
+    #[inline]
+    pub const fn control_bits(self) -> u32 {
+        self.raw & 0xFF
+    }
+
+    /// Checks if two PSRs have the same control bits, ignoring the condition
+    /// flags.
+    #[inline]
+    pub const fn same_control(self, other: Psr) -> bool {
+        self.control_bits() == other.control_bits()
+    }
+
     /// Returns the current operating mode (bits 4-0).
     #[inline]
     pub fn mode(self) -> CpuMode {
-        match CpuMode::try_from(self.raw.bits::<0, 5>() as u8) {
+        match CpuMode::from_psr_bits(self.raw as u8) {
             Ok(mode) => mode,
-            Err(err) => invalid_cpu_mode(err.value()),
+            Err(bits) => invalid_cpu_mode(bits),
         }
     }
 
+    /// Returns the current state (bit 5).
     #[inline]
     pub fn state(self) -> CpuState {
         CpuState::from(self.raw.bit::<5>())
     }
 
+    /// Sets the current operating mode (bits 4-0).
+    #[inline]
+    pub fn set_mode(&mut self, mode: CpuMode) {
+        self.raw = self.raw.set_bits::<0, 5>(u8::from(mode).into());
+    }
+
+    /// Sets the current state (bit 5).
+    #[inline]
+    pub fn set_state(&mut self, state: CpuState) {
+        self.raw = self.raw.set_bit::<5>(state.into());
+    }
+
     /// Checks if fast interrupt requests are disabled.
     #[inline]
     pub fn fiq_disabled(self) -> bool {
@@ -47,6 +161,18 @@ impl Psr {
         self.raw.bit::<7>()
     }
 
+    /// Sets whether fast interrupt requests are disabled.
+    #[inline]
+    pub fn set_fiq_disabled(&mut self, disabled: bool) {
+        self.raw = self.raw.set_bit::<6>(disabled);
+    }
+
+    /// Sets whether regular interrupt requests are disabled.
+    #[inline]
+    pub fn set_irq_disabled(&mut self, disabled: bool) {
+        self.raw = self.raw.set_bit::<7>(disabled);
+    }
+
     /// Checks the overflow flag (V).
     #[inline]
     #[allow(non_snake_case)]
@@ -74,6 +200,34 @@ impl Psr {
     pub fn N(self) -> bool {
         self.raw.bit::<31>()
     }
+
+    /// Sets the overflow flag (V).
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn set_V(&mut self, v: bool) {
+        self.raw = self.raw.set_bit::<28>(v);
+    }
+
+    /// Sets the carry flag (C).
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn set_C(&mut self, c: bool) {
+        self.raw = self.raw.set_bit::<29>(c);
+    }
+
+    /// Sets the zero flag (Z).
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn set_Z(&mut self, z: bool) {
+        self.raw = self.raw.set_bit::<30>(z);
+    }
+
+    /// Sets the sign flag (N).
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn set_N(&mut self, n: bool) {
+        self.raw = self.raw.set_bit::<31>(n);
+    }
 }
 
 impl fmt::Display for Psr {
@@ -129,3 +283,68 @@ impl From<CpuState> for bool {
 fn invalid_cpu_mode(mode: u8) -> ! {
     panic!("invalid cpu mode: {:05b}", mode)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_round_trip() {
+        let values = [
+            0x0000_001F, // System, ARM.
+            0x0000_00D3, // Supervisor, ARM, IRQ/FIQ disabled.
+            0x6000_0030, // User, THUMB, Z and C set.
+            0x9000_0092, // IRQ, ARM, IRQ disabled, N and V set.
+            0xF000_0071, // FIQ, THUMB, FIQ disabled, all flags set.
+        ];
+
+        for raw in values {
+            let psr = Psr::from_raw(raw);
+            assert_eq!(Psr::from_fields(psr.to_fields()), psr, "{:#010X}", raw);
+        }
+    }
+
+    #[test]
+    fn with_flags() {
+        let psr = Psr::with_flags(true, false, true, false);
+        assert!(psr.N() && !psr.Z() && psr.C() && !psr.V());
+        assert_eq!(psr.mode(), CpuMode::User);
+        assert_eq!(psr.state(), CpuState::Arm);
+        assert_eq!(psr.raw(), 0xA000_0010);
+
+        assert_eq!(Psr::with_flags(false, true, false, true).raw(), 0x5000_0010);
+    }
+
+    #[test]
+    fn fields() {
+        let fields = Psr::from_raw(0x6000_0030).to_fields();
+
+        assert_eq!(
+            fields,
+            PsrFields {
+                mode: CpuMode::User,
+                state: CpuState::Thumb,
+                fiq_disabled: false,
+                irq_disabled: false,
+                v: false,
+                c: true,
+                z: true,
+                n: false,
+            }
+        );
+    }
+
+    #[test]
+    fn same_control() {
+        let a = Psr::from_raw(0x0000_00DF);
+        let b = Psr::from_raw(0xF000_00DF);
+        assert_eq!(a.control_bits(), 0xDF);
+        assert_eq!(b.control_bits(), 0xDF);
+        assert!(a.same_control(b));
+
+        // IRQ mode.
+        assert!(!a.same_control(Psr::from_raw(0x0000_00D2)));
+        // THUMB state.
+        assert!(!a.same_control(Psr::from_raw(0x0000_00FF)));
+    }
+}
@@ -0,0 +1,168 @@
+//! Register file model.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#armcpuregisterset>
+
+use crate::psr::Psr;
+use crate::CpuMode;
+
+/// The register file, including the registers banked for each mode.
+///
+/// The 16 registers visible in the current mode are kept in place, and banked
+/// registers are swapped in and out when switching modes.
+///
+/// # Register Banks
+///
+/// ```text
+/// System/User  FIQ       Supervisor  Abort     IRQ       Undefined
+/// --------------------------------------------------------------
+/// R0           R0        R0          R0        R0        R0
+/// ...          ...       ...         ...       ...       ...
+/// R7           R7        R7          R7        R7        R7
+/// --------------------------------------------------------------
+/// R8           R8_fiq    R8          R8        R8        R8
+/// ...          ...       ...         ...       ...       ...
+/// R12          R12_fiq   R12         R12       R12       R12
+/// --------------------------------------------------------------
+/// R13 (SP)     R13_fiq   R13_svc     R13_abt   R13_irq   R13_und
+/// R14 (LR)     R14_fiq   R14_svc     R14_abt   R14_irq   R14_und
+/// --------------------------------------------------------------
+/// R15 (PC)     R15       R15         R15       R15       R15
+/// --------------------------------------------------------------
+/// CPSR         CPSR      CPSR        CPSR      CPSR      CPSR
+/// --           SPSR_fiq  SPSR_svc    SPSR_abt  SPSR_irq  SPSR_und
+/// ```
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#armcpuregisterset>
+#[derive(Clone, Debug, Default)]
+pub struct Registers {
+    /// Registers visible in the current mode.
+    current: [u32; 16],
+    /// R8-R12 of all modes other than FIQ, while in FIQ mode.
+    r8_r12: [u32; 5],
+    /// R8-R12 of FIQ mode, while not in FIQ mode.
+    r8_r12_fiq: [u32; 5],
+    /// R13-R14 of each bank, while not in a mode using that bank.
+    r13_r14: [[u32; 2]; 6],
+    /// SPSR of each bank, the User/System bank has no SPSR.
+    spsr: [Psr; 6],
+}
+
+/// Returns the register bank used by a mode.
+#[inline]
+fn bank(mode: CpuMode) -> usize {
+    match mode {
+        CpuMode::User | CpuMode::System => 0,
+        CpuMode::Fiq => 1,
+        CpuMode::Irq => 2,
+        CpuMode::Supervisor => 3,
+        CpuMode::Abort => 4,
+        CpuMode::Undefined => 5,
+    }
+}
+
+impl Registers {
+    /// Returns the value of register `n` in the current mode.
+    #[inline]
+    pub fn get(&self, n: u8) -> u32 {
+        self.current[usize::from(n)]
+    }
+
+    /// Sets the value of register `n` in the current mode.
+    #[inline]
+    pub fn set(&mut self, n: u8, value: u32) {
+        self.current[usize::from(n)] = value;
+    }
+
+    /// Returns the SPSR of `mode`, or `None` if the mode has no SPSR.
+    #[inline]
+    pub fn spsr(&self, mode: CpuMode) -> Option<Psr> {
+        match bank(mode) {
+            0 => None,
+            bank => Some(self.spsr[bank]),
+        }
+    }
+
+    /// Sets the SPSR of `mode`, ignored if the mode has no SPSR.
+    #[inline]
+    pub fn set_spsr(&mut self, mode: CpuMode, psr: Psr) {
+        match bank(mode) {
+            0 => {}
+            bank => self.spsr[bank] = psr,
+        }
+    }
+
+    /// Swaps banked registers when switching from mode `from` to mode `to`.
+    pub fn switch_mode(&mut self, from: CpuMode, to: CpuMode) {
+        let (from, to) = (bank(from), bank(to));
+        if from == to {
+            return;
+        }
+
+        self.r13_r14[from].copy_from_slice(&self.current[13..15]);
+        self.current[13..15].copy_from_slice(&self.r13_r14[to]);
+
+        const FIQ: usize = 1;
+        if from == FIQ {
+            self.r8_r12_fiq.copy_from_slice(&self.current[8..13]);
+            self.current[8..13].copy_from_slice(&self.r8_r12);
+        } else if to == FIQ {
+            self.r8_r12.copy_from_slice(&self.current[8..13]);
+            self.current[8..13].copy_from_slice(&self.r8_r12_fiq);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fiq_banks_r8_r12() {
+        let mut regs = Registers::default();
+        for n in 8..=14 {
+            regs.set(n, u32::from(n));
+        }
+
+        regs.switch_mode(CpuMode::User, CpuMode::Fiq);
+        for n in 8..=14 {
+            assert_eq!(regs.get(n), 0, "r{}", n);
+            regs.set(n, 0xF0 + u32::from(n));
+        }
+
+        regs.switch_mode(CpuMode::Fiq, CpuMode::User);
+        for n in 8..=14 {
+            assert_eq!(regs.get(n), u32::from(n), "r{}", n);
+        }
+
+        regs.switch_mode(CpuMode::User, CpuMode::Fiq);
+        for n in 8..=14 {
+            assert_eq!(regs.get(n), 0xF0 + u32::from(n), "r{}", n);
+        }
+    }
+
+    #[test]
+    fn other_modes_share_r8_r12() {
+        let mut regs = Registers::default();
+        for n in 8..=12 {
+            regs.set(n, u32::from(n));
+        }
+
+        for mode in [
+            CpuMode::Irq,
+            CpuMode::Supervisor,
+            CpuMode::Abort,
+            CpuMode::Undefined,
+            CpuMode::System,
+        ] {
+            regs.switch_mode(CpuMode::User, mode);
+            for n in 8..=12 {
+                assert_eq!(regs.get(n), u32::from(n), "{:?} r{}", mode, n);
+            }
+            regs.switch_mode(mode, CpuMode::User);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Helpers for testing code using the CPU, enabled by the `test-util`
+//! feature.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::Memory;
+
+/// A flat 32-bit address space, where unwritten bytes read as zero.
+#[derive(Debug, Default)]
+pub struct FlatMemory {
+    bytes: HashMap<u32, u8>,
+}
+
+impl FlatMemory {
+    /// Creates an empty address space.
+    pub fn new() -> FlatMemory {
+        FlatMemory::default()
+    }
+}
+
+impl Memory for FlatMemory {
+    fn read8(&self, addr: u32) -> u8 {
+        self.bytes.get(&addr).copied().unwrap_or(0)
+    }
+
+    fn write8(&mut self, addr: u32, value: u8) {
+        self.bytes.insert(addr, value);
+    }
+}
+
+/// Writes `bytes` to consecutive addresses starting at `addr`.
+///
+/// Memory is little-endian, so words read back from a slice built with
+/// `to_le_bytes` have their original value, see [`arm_program`] and
+/// [`thumb_program`].
+pub fn load_into(mem: &mut impl Memory, addr: u32, bytes: &[u8]) {
+    for (i, &byte) in bytes.iter().enumerate() {
+        mem.write8(addr.wrapping_add(i as u32), byte);
+    }
+}
+
+/// Returns the bytes of a program of ARM opcodes.
+pub fn arm_program(opcodes: &[u32]) -> Vec<u8> {
+    opcodes
+        .iter()
+        .flat_map(|opcode| opcode.to_le_bytes())
+        .collect()
+}
+
+/// Returns the bytes of a program of THUMB opcodes.
+pub fn thumb_program(opcodes: &[u16]) -> Vec<u8> {
+    opcodes
+        .iter()
+        .flat_map(|opcode| opcode.to_le_bytes())
+        .collect()
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<(Level, String)>>> = const { RefCell::new(None) };
+}
+
+/// A logger recording messages logged on threads running [`capture_logs`].
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        CAPTURED.with(|captured| {
+            if let Some(logs) = captured.borrow_mut().as_mut() {
+                logs.push((record.level(), record.args().to_string()));
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs `f`, returning the messages logged on the current thread while it
+/// runs.
+///
+/// The first call installs the global logger, so it must not be set
+/// elsewhere.
+pub fn capture_logs(f: impl FnOnce()) -> Vec<(Level, String)> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CaptureLogger).expect("logger already set");
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    f();
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_into_reads_back() {
+        let mut mem = FlatMemory::new();
+        load_into(
+            &mut mem,
+            0x0800_0000,
+            &arm_program(&[0xE3A0_0003, 0xE250_0001]),
+        );
+        assert_eq!(mem.read32(0x0800_0000), 0xE3A0_0003);
+        assert_eq!(mem.read32(0x0800_0004), 0xE250_0001);
+        assert_eq!(mem.read16(0x0800_0002), 0xE3A0);
+        assert_eq!(mem.read8(0x0800_0008), 0);
+
+        load_into(&mut mem, 0x0800_0010, &thumb_program(&[0x2003, 0x3801]));
+        assert_eq!(mem.read32(0x0800_0010), 0x3801_2003);
+
+        load_into(&mut mem, 0x0800_0020, &[0x12, 0x34]);
+        assert_eq!(mem.read16(0x0800_0020), 0x3412);
+        assert_eq!(mem.read8(0x0800_0022), 0);
+    }
+}
@@ -0,0 +1,264 @@
+//! THUMB disassembler.
+
+use std::fmt;
+
+use crate::disasm::{Address, Imm, Reg, RegList, Relative, Target};
+use crate::thumb::{
+    decode, AddSubOperand, AluOp, HiRegisterOp, ImmediateOp, Instruction, SignedOp,
+};
+
+/// Disassembles a THUMB opcode.
+///
+/// Branch targets are written relative to the address of the branch
+/// instruction.
+pub fn disassemble(opcode: u16) -> String {
+    decode(opcode).to_string()
+}
+
+/// Disassembles a THUMB opcode at `address`.
+///
+/// Branch targets and PC-relative load addresses are resolved to absolute
+/// addresses, accounting for `PC` reading 4 bytes ahead of the instruction.
+/// The halves of a long branch with link are not resolved, as the target
+/// depends on both.
+pub fn disassemble_at(opcode: u16, address: u32) -> String {
+    decode(opcode).display_at(address).to_string()
+}
+
+/// The offset of `PC` from the executing instruction.
+const PC_OFFSET: u32 = 4;
+
+struct Disasm<'a> {
+    instr: &'a Instruction,
+    address: Option<u32>,
+}
+
+/// Formats the instruction in assembly syntax, with branch targets written
+/// relative to the address of the branch instruction.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Disasm {
+            instr: self,
+            address: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Instruction {
+    /// Returns a value formatting the instruction at `address`, as with
+    /// [`disassemble_at`].
+    pub fn display_at(&self, address: u32) -> impl fmt::Display + '_ {
+        Disasm {
+            instr: self,
+            address: Some(address),
+        }
+    }
+}
+
+impl fmt::Display for Disasm<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.instr {
+            Instruction::MoveShifted { op, offset, rs, rd } => {
+                write!(f, "{} {}, {}, #{}", op, Reg(rd), Reg(rs), offset)
+            }
+            Instruction::AddSubtract {
+                subtract,
+                operand,
+                rs,
+                rd,
+            } => {
+                let mnemonic = if subtract { "SUB" } else { "ADD" };
+                write!(f, "{} {}, {}, ", mnemonic, Reg(rd), Reg(rs))?;
+                match operand {
+                    AddSubOperand::Register(rn) => write!(f, "{}", Reg(rn)),
+                    AddSubOperand::Immediate(imm) => write!(f, "{}", Imm(imm.into())),
+                }
+            }
+            Instruction::Immediate { op, rd, offset } => {
+                let mnemonic = match op {
+                    ImmediateOp::Mov => "MOV",
+                    ImmediateOp::Cmp => "CMP",
+                    ImmediateOp::Add => "ADD",
+                    ImmediateOp::Sub => "SUB",
+                };
+                write!(f, "{} {}, {}", mnemonic, Reg(rd), Imm(offset.into()))
+            }
+            Instruction::Alu { op, rs, rd } => {
+                write!(f, "{} {}, {}", alu_mnemonic(op), Reg(rd), Reg(rs))
+            }
+            Instruction::HiRegister { op, rs, rd } => {
+                let mnemonic = match op {
+                    HiRegisterOp::Add => "ADD",
+                    HiRegisterOp::Cmp => "CMP",
+                    HiRegisterOp::Mov => "MOV",
+                };
+                write!(f, "{} {}, {}", mnemonic, Reg(rd), Reg(rs))
+            }
+            Instruction::BranchExchange { rs } => write!(f, "BX {}", Reg(rs)),
+            Instruction::PcRelativeLoad { rd, offset } => match self.address {
+                Some(address) => {
+                    // `PC` is word aligned for the load.
+                    let pc = address.wrapping_add(PC_OFFSET) & !0b11;
+                    let target = pc.wrapping_add(u32::from(offset) * 4);
+                    write!(f, "LDR {}, [{}]", Reg(rd), Address(target))
+                }
+                None => write!(f, "LDR {}, [pc, {}]", Reg(rd), Imm(u32::from(offset) * 4)),
+            },
+            Instruction::LoadStoreRegister {
+                load,
+                byte,
+                ro,
+                rb,
+                rd,
+            } => {
+                let mnemonic = if load { "LDR" } else { "STR" };
+                let b = if byte { "B" } else { "" };
+                write!(
+                    f,
+                    "{}{} {}, [{}, {}]",
+                    mnemonic,
+                    b,
+                    Reg(rd),
+                    Reg(rb),
+                    Reg(ro)
+                )
+            }
+            Instruction::LoadStoreSigned { op, ro, rb, rd } => {
+                let mnemonic = match op {
+                    SignedOp::Strh => "STRH",
+                    SignedOp::Ldsb => "LDSB",
+                    SignedOp::Ldrh => "LDRH",
+                    SignedOp::Ldsh => "LDSH",
+                };
+                write!(f, "{} {}, [{}, {}]", mnemonic, Reg(rd), Reg(rb), Reg(ro))
+            }
+            Instruction::LoadStoreImmediate {
+                load,
+                byte,
+                offset,
+                rb,
+                rd,
+            } => {
+                let mnemonic = if load { "LDR" } else { "STR" };
+                let (b, scale) = if byte { ("B", 1) } else { ("", 4) };
+                let offset = Imm(u32::from(offset) * scale);
+                write!(
+                    f,
+                    "{}{} {}, [{}, {}]",
+                    mnemonic,
+                    b,
+                    Reg(rd),
+                    Reg(rb),
+                    offset
+                )
+            }
+            Instruction::LoadStoreHalfword {
+                load,
+                offset,
+                rb,
+                rd,
+            } => {
+                let mnemonic = if load { "LDRH" } else { "STRH" };
+                let offset = Imm(u32::from(offset) * 2);
+                write!(f, "{} {}, [{}, {}]", mnemonic, Reg(rd), Reg(rb), offset)
+            }
+            Instruction::SpRelative { load, rd, offset } => {
+                let mnemonic = if load { "LDR" } else { "STR" };
+                write!(
+                    f,
+                    "{} {}, [sp, {}]",
+                    mnemonic,
+                    Reg(rd),
+                    Imm(u32::from(offset) * 4)
+                )
+            }
+            Instruction::LoadAddress { sp, rd, offset } => {
+                let base = if sp { "sp" } else { "pc" };
+                write!(
+                    f,
+                    "ADD {}, {}, {}",
+                    Reg(rd),
+                    base,
+                    Imm(u32::from(offset) * 4)
+                )
+            }
+            Instruction::AddSp { negative, offset } => {
+                let sign = if negative { "-" } else { "" };
+                write!(f, "ADD sp, #{}{:#X}", sign, u32::from(offset) * 4)
+            }
+            Instruction::PushPop {
+                pop,
+                pc_lr,
+                registers,
+            } => {
+                let mut registers = u16::from(registers);
+                if pc_lr {
+                    registers |= if pop { 1 << 15 } else { 1 << 14 };
+                }
+
+                let mnemonic = if pop { "POP" } else { "PUSH" };
+                write!(f, "{} {}", mnemonic, RegList(registers))
+            }
+            Instruction::MultipleLoadStore {
+                load,
+                rb,
+                registers,
+            } => {
+                let mnemonic = if load { "LDMIA" } else { "STMIA" };
+                write!(
+                    f,
+                    "{} {}!, {}",
+                    mnemonic,
+                    Reg(rb),
+                    RegList(registers.into())
+                )
+            }
+            Instruction::ConditionalBranch { cond, offset } => {
+                let target = Target {
+                    offset: i32::from(offset) * 2 + PC_OFFSET as i32,
+                    address: self.address,
+                };
+                write!(f, "B{} {}", cond, target)
+            }
+            Instruction::SoftwareInterrupt { comment } => {
+                write!(f, "SWI {}", Imm(comment.into()))
+            }
+            Instruction::Branch { offset } => {
+                let target = Target {
+                    offset: i32::from(offset) * 2 + PC_OFFSET as i32,
+                    address: self.address,
+                };
+                write!(f, "B {}", target)
+            }
+            Instruction::BranchLinkPrefix { offset } => {
+                write!(f, "BL {} (prefix)", Relative((i32::from(offset) << 12) + 4))
+            }
+            Instruction::BranchLinkSuffix { offset } => {
+                write!(f, "BL lr, {} (suffix)", Imm(u32::from(offset) * 2))
+            }
+            Instruction::Undefined => f.write_str("UND"),
+        }
+    }
+}
+
+fn alu_mnemonic(op: AluOp) -> &'static str {
+    match op {
+        AluOp::And => "AND",
+        AluOp::Eor => "EOR",
+        AluOp::Lsl => "LSL",
+        AluOp::Lsr => "LSR",
+        AluOp::Asr => "ASR",
+        AluOp::Adc => "ADC",
+        AluOp::Sbc => "SBC",
+        AluOp::Ror => "ROR",
+        AluOp::Tst => "TST",
+        AluOp::Neg => "NEG",
+        AluOp::Cmp => "CMP",
+        AluOp::Cmn => "CMN",
+        AluOp::Orr => "ORR",
+        AluOp::Mul => "MUL",
+        AluOp::Bic => "BIC",
+        AluOp::Mvn => "MVN",
+    }
+}
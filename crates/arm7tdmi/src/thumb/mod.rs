@@ -0,0 +1,639 @@
+//! THUMB (16-bit) instruction set.
+//!
+//! # Sources
+//!
+//! \[1\]: <https://problemkaputt.de/gbatek.htm#thumbinstructionsummary>
+
+use crate::bit::BitIndex;
+use crate::{Cond, CpuState, FlagSet, ShiftType};
+
+mod disasm;
+mod opcode;
+
+pub use self::disasm::{disassemble, disassemble_at};
+pub use self::opcode::ThumbOpcode;
+
+/// A decoded THUMB instruction.
+///
+/// Register operands are stored as register indices, in the range `0..8`
+/// unless otherwise noted. Immediate offsets are stored unscaled, as they are
+/// encoded in the opcode.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbinstructionsummary>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    /// Format 1: move shifted register (`LSL`, `LSR`, `ASR`).
+    MoveShifted {
+        op: ShiftType,
+        /// Shift amount (`0..=32`).
+        offset: u8,
+        rs: u8,
+        rd: u8,
+    },
+    /// Format 2: add/subtract (`ADD`, `SUB`).
+    AddSubtract {
+        subtract: bool,
+        operand: AddSubOperand,
+        rs: u8,
+        rd: u8,
+    },
+    /// Format 3: move/compare/add/subtract immediate (`MOV`, `CMP`, `ADD`,
+    /// `SUB`).
+    Immediate { op: ImmediateOp, rd: u8, offset: u8 },
+    /// Format 4: ALU operations.
+    Alu { op: AluOp, rs: u8, rd: u8 },
+    /// Format 5: hi register operations (`ADD`, `CMP`, `MOV`).
+    ///
+    /// Registers are in the range `0..16`.
+    HiRegister { op: HiRegisterOp, rs: u8, rd: u8 },
+    /// Format 5: branch and exchange (`BX`).
+    ///
+    /// The register is in the range `0..16`.
+    BranchExchange { rs: u8 },
+    /// Format 6: PC-relative load (`LDR`).
+    PcRelativeLoad {
+        rd: u8,
+        /// Word offset.
+        offset: u8,
+    },
+    /// Format 7: load/store with register offset (`STR`, `STRB`, `LDR`,
+    /// `LDRB`).
+    LoadStoreRegister {
+        load: bool,
+        byte: bool,
+        ro: u8,
+        rb: u8,
+        rd: u8,
+    },
+    /// Format 8: load/store sign-extended byte/halfword (`STRH`, `LDSB`,
+    /// `LDRH`, `LDSH`).
+    LoadStoreSigned {
+        op: SignedOp,
+        ro: u8,
+        rb: u8,
+        rd: u8,
+    },
+    /// Format 9: load/store with immediate offset (`STR`, `LDR`, `STRB`,
+    /// `LDRB`).
+    LoadStoreImmediate {
+        load: bool,
+        byte: bool,
+        /// Word offset, or byte offset if `byte` is set.
+        offset: u8,
+        rb: u8,
+        rd: u8,
+    },
+    /// Format 10: load/store halfword (`STRH`, `LDRH`).
+    LoadStoreHalfword {
+        load: bool,
+        /// Halfword offset.
+        offset: u8,
+        rb: u8,
+        rd: u8,
+    },
+    /// Format 11: SP-relative load/store (`STR`, `LDR`).
+    SpRelative {
+        load: bool,
+        rd: u8,
+        /// Word offset.
+        offset: u8,
+    },
+    /// Format 12: load address (`ADD Rd, PC, #nn`, `ADD Rd, SP, #nn`).
+    LoadAddress {
+        /// Source is `SP`, rather than `PC`.
+        sp: bool,
+        rd: u8,
+        /// Word offset.
+        offset: u8,
+    },
+    /// Format 13: add offset to stack pointer (`ADD SP, #nn`).
+    AddSp {
+        negative: bool,
+        /// Word offset.
+        offset: u8,
+    },
+    /// Format 14: push/pop registers (`PUSH`, `POP`).
+    PushPop {
+        pop: bool,
+        /// Also store `LR` or load `PC`.
+        pc_lr: bool,
+        /// Register list, bit `n` is set if `Rn` is transferred.
+        registers: u8,
+    },
+    /// Format 15: multiple load/store (`STMIA`, `LDMIA`).
+    MultipleLoadStore {
+        load: bool,
+        rb: u8,
+        /// Register list, bit `n` is set if `Rn` is transferred.
+        registers: u8,
+    },
+    /// Format 16: conditional branch (`B{cond}`).
+    ConditionalBranch {
+        cond: Cond,
+        /// Signed halfword offset, relative to `PC` (the instruction address +
+        /// 4).
+        offset: i8,
+    },
+    /// Format 17: software interrupt (`SWI`).
+    SoftwareInterrupt {
+        /// Comment field, ignored by the processor.
+        comment: u8,
+    },
+    /// Format 18: unconditional branch (`B`).
+    Branch {
+        /// Signed 11-bit halfword offset, relative to `PC` (the instruction
+        /// address + 4).
+        offset: i16,
+    },
+    /// Format 19: long branch with link (`BL`), first instruction.
+    BranchLinkPrefix {
+        /// Signed 11-bit upper half of the halfword offset.
+        offset: i16,
+    },
+    /// Format 19: long branch with link (`BL`), second instruction.
+    BranchLinkSuffix {
+        /// 11-bit lower half of the halfword offset.
+        offset: u16,
+    },
+    /// Undefined instruction.
+    Undefined,
+}
+
+impl Instruction {
+    /// Returns the size of the instruction in bytes, always 2.
+    ///
+    /// The two halves of a long branch with link are separate instructions,
+    /// executed one after the other, so each is 2 bytes.
+    #[inline]
+    pub fn length(&self) -> u32 {
+        2
+    }
+
+    /// Returns the state the instruction is executed in, always
+    /// [`CpuState::Thumb`].
+    #[inline]
+    pub fn executes_in_state(&self) -> CpuState {
+        CpuState::Thumb
+    }
+
+    /// Returns the address of the next instruction, if the instruction at
+    /// `addr` does not branch.
+    ///
+    /// For the prefix of a long branch with link, this is the address of the
+    /// suffix.
+    #[inline]
+    pub fn next_pc(&self, addr: u32) -> u32 {
+        addr.wrapping_add(self.length())
+    }
+
+    /// Returns the flags that may be updated by the instruction.
+    pub fn affects_flags(&self) -> FlagSet {
+        match *self {
+            Instruction::MoveShifted {
+                op: ShiftType::Lsl,
+                offset: 0,
+                ..
+            } => FlagSet::NZ,
+            Instruction::MoveShifted { .. } => FlagSet::NZC,
+            Instruction::AddSubtract { .. } => FlagSet::NZCV,
+            Instruction::Immediate {
+                op: ImmediateOp::Mov,
+                ..
+            } => FlagSet::NZ,
+            Instruction::Immediate { .. } => FlagSet::NZCV,
+            Instruction::Alu { op, .. } => match op {
+                AluOp::And
+                | AluOp::Eor
+                | AluOp::Tst
+                | AluOp::Orr
+                | AluOp::Mul
+                | AluOp::Bic
+                | AluOp::Mvn => FlagSet::NZ,
+                AluOp::Lsl | AluOp::Lsr | AluOp::Asr | AluOp::Ror => FlagSet::NZC,
+                AluOp::Adc | AluOp::Sbc | AluOp::Neg | AluOp::Cmp | AluOp::Cmn => FlagSet::NZCV,
+            },
+            Instruction::HiRegister {
+                op: HiRegisterOp::Cmp,
+                ..
+            } => FlagSet::NZCV,
+            _ => FlagSet::NONE,
+        }
+    }
+}
+
+/// The second operand of an add/subtract (format 2).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddSubOperand {
+    /// A register.
+    Register(u8),
+    /// A 3-bit immediate.
+    Immediate(u8),
+}
+
+/// Move/compare/add/subtract immediate operations (format 3).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImmediateOp {
+    Mov,
+    Cmp,
+    Add,
+    Sub,
+}
+
+/// ALU operations (format 4).
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbopcodesregisteroperationsaluhiregisteroperations>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AluOp {
+    /// `Rd = Rd AND Rs`.
+    And,
+    /// `Rd = Rd XOR Rs`.
+    Eor,
+    /// `Rd = Rd << (Rs AND 0xFF)`.
+    Lsl,
+    /// `Rd = Rd >> (Rs AND 0xFF)`.
+    Lsr,
+    /// `Rd = Rd SAR (Rs AND 0xFF)`.
+    Asr,
+    /// `Rd = Rd + Rs + C`.
+    Adc,
+    /// `Rd = Rd - Rs - NOT C`.
+    Sbc,
+    /// `Rd = Rd ROR (Rs AND 0xFF)`.
+    Ror,
+    /// `Void = Rd AND Rs`.
+    Tst,
+    /// `Rd = 0 - Rs`.
+    Neg,
+    /// `Void = Rd - Rs`.
+    Cmp,
+    /// `Void = Rd + Rs`.
+    Cmn,
+    /// `Rd = Rd OR Rs`.
+    Orr,
+    /// `Rd = Rd * Rs`.
+    Mul,
+    /// `Rd = Rd AND NOT Rs`.
+    Bic,
+    /// `Rd = NOT Rs`.
+    Mvn,
+}
+
+/// Hi register operations (format 5).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HiRegisterOp {
+    Add,
+    Cmp,
+    Mov,
+}
+
+/// Load/store sign-extended byte/halfword operations (format 8).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignedOp {
+    /// Store halfword.
+    Strh,
+    /// Load sign-extended byte.
+    Ldsb,
+    /// Load zero-extended halfword.
+    Ldrh,
+    /// Load sign-extended halfword.
+    Ldsh,
+}
+
+/// Decodes a THUMB opcode.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbinstructionsummary>
+pub fn decode(opcode: u16) -> Instruction {
+    let fields = ThumbOpcode(opcode);
+    let rd = fields.rd();
+    let rs = fields.rs();
+
+    match opcode.bits::<13, 16>() {
+        0b000 if opcode.bits::<11, 13>() == 0b11 => {
+            let operand = if opcode.bit::<10>() {
+                AddSubOperand::Immediate(fields.rn())
+            } else {
+                AddSubOperand::Register(fields.rn())
+            };
+            Instruction::AddSubtract {
+                subtract: opcode.bit::<9>(),
+                operand,
+                rs,
+                rd,
+            }
+        }
+        0b000 => {
+            let (op, offset) =
+                crate::arm::immediate_shift(opcode.bits::<11, 13>().into(), fields.offset5());
+            Instruction::MoveShifted { op, offset, rs, rd }
+        }
+        0b001 => {
+            let op = match opcode.bits::<11, 13>() {
+                0b00 => ImmediateOp::Mov,
+                0b01 => ImmediateOp::Cmp,
+                0b10 => ImmediateOp::Add,
+                _ => ImmediateOp::Sub,
+            };
+            Instruction::Immediate {
+                op,
+                rd: fields.rd_hi(),
+                offset: fields.immediate(),
+            }
+        }
+        0b010 => match opcode.bits::<10, 13>() {
+            0b000 => Instruction::Alu {
+                op: alu_op(opcode.bits::<6, 10>()),
+                rs,
+                rd,
+            },
+            0b001 => decode_hi_register(opcode),
+            0b010 | 0b011 => Instruction::PcRelativeLoad {
+                rd: fields.rd_hi(),
+                offset: fields.immediate(),
+            },
+            _ => {
+                let ro = fields.rn();
+                if opcode.bit::<9>() {
+                    let op = match opcode.bits::<10, 12>() {
+                        0b00 => SignedOp::Strh,
+                        0b01 => SignedOp::Ldsb,
+                        0b10 => SignedOp::Ldrh,
+                        _ => SignedOp::Ldsh,
+                    };
+                    Instruction::LoadStoreSigned { op, ro, rb: rs, rd }
+                } else {
+                    Instruction::LoadStoreRegister {
+                        load: opcode.bit::<11>(),
+                        byte: opcode.bit::<10>(),
+                        ro,
+                        rb: rs,
+                        rd,
+                    }
+                }
+            }
+        },
+        0b011 => Instruction::LoadStoreImmediate {
+            load: opcode.bit::<11>(),
+            byte: opcode.bit::<12>(),
+            offset: fields.offset5(),
+            rb: rs,
+            rd,
+        },
+        0b100 if !opcode.bit::<12>() => Instruction::LoadStoreHalfword {
+            load: opcode.bit::<11>(),
+            offset: fields.offset5(),
+            rb: rs,
+            rd,
+        },
+        0b100 => Instruction::SpRelative {
+            load: opcode.bit::<11>(),
+            rd: fields.rd_hi(),
+            offset: fields.immediate(),
+        },
+        0b101 if !opcode.bit::<12>() => Instruction::LoadAddress {
+            sp: opcode.bit::<11>(),
+            rd: fields.rd_hi(),
+            offset: fields.immediate(),
+        },
+        0b101 => match opcode.bits::<8, 12>() {
+            0b0000 => Instruction::AddSp {
+                negative: opcode.bit::<7>(),
+                offset: opcode.bits::<0, 7>() as u8,
+            },
+            0b0100 | 0b0101 | 0b1100 | 0b1101 => Instruction::PushPop {
+                pop: opcode.bit::<11>(),
+                pc_lr: opcode.bit::<8>(),
+                registers: fields.immediate(),
+            },
+            _ => Instruction::Undefined,
+        },
+        0b110 if !opcode.bit::<12>() => Instruction::MultipleLoadStore {
+            load: opcode.bit::<11>(),
+            rb: fields.rd_hi(),
+            registers: fields.immediate(),
+        },
+        // Conditions `AL` and `NV` are reserved, for undefined instructions
+        // and `SWI` respectively.
+        0b110 => match opcode.bits::<8, 12>() {
+            0b1110 => Instruction::Undefined,
+            0b1111 => Instruction::SoftwareInterrupt {
+                comment: fields.immediate(),
+            },
+            cond => Instruction::ConditionalBranch {
+                cond: match Cond::try_from(cond as u8) {
+                    Ok(cond) => cond,
+                    Err(_) => unreachable!("all condition values are valid"),
+                },
+                offset: opcode as u8 as i8,
+            },
+        },
+        _ => match opcode.bits::<11, 13>() {
+            0b00 => Instruction::Branch {
+                offset: ((opcode << 5) as i16) >> 5,
+            },
+            0b10 => Instruction::BranchLinkPrefix {
+                offset: ((opcode << 5) as i16) >> 5,
+            },
+            0b11 => Instruction::BranchLinkSuffix {
+                offset: opcode.bits::<0, 11>(),
+            },
+            _ => Instruction::Undefined,
+        },
+    }
+}
+
+/// Decodes hi register operations and `BX` (format 5).
+fn decode_hi_register(opcode: u16) -> Instruction {
+    let rd = register(opcode.bits::<0, 3>() | (opcode.bits::<7, 8>() << 3));
+    let rs = register(opcode.bits::<3, 7>());
+
+    let op = match opcode.bits::<8, 10>() {
+        0b00 => HiRegisterOp::Add,
+        0b01 => HiRegisterOp::Cmp,
+        0b10 => HiRegisterOp::Mov,
+        _ => return Instruction::BranchExchange { rs },
+    };
+    Instruction::HiRegister { op, rs, rd }
+}
+
+/// Decodes a 3-bit low register index field.
+fn low_register(bits: u16) -> u8 {
+    debug_assert!(bits < 8, "low register index out of range: {}", bits);
+
+    bits as u8
+}
+
+/// Decodes a 4-bit register index field of a hi register operation.
+fn register(bits: u16) -> u8 {
+    debug_assert!(bits < 16, "register index out of range: {}", bits);
+
+    bits as u8
+}
+
+/// Decodes a 4-bit ALU operation field (format 4).
+fn alu_op(bits: u16) -> AluOp {
+    debug_assert!(bits < 0x10, "ALU operation out of range: {:#X}", bits);
+
+    match bits {
+        0x0 => AluOp::And,
+        0x1 => AluOp::Eor,
+        0x2 => AluOp::Lsl,
+        0x3 => AluOp::Lsr,
+        0x4 => AluOp::Asr,
+        0x5 => AluOp::Adc,
+        0x6 => AluOp::Sbc,
+        0x7 => AluOp::Ror,
+        0x8 => AluOp::Tst,
+        0x9 => AluOp::Neg,
+        0xA => AluOp::Cmp,
+        0xB => AluOp::Cmn,
+        0xC => AluOp::Orr,
+        0xD => AluOp::Mul,
+        0xE => AluOp::Bic,
+        _ => AluOp::Mvn,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length() {
+        // ADD r0, r1, r2
+        let instr = decode(0x1888);
+        assert_eq!(instr.length(), 2);
+        assert_eq!(instr.next_pc(0x0800_0000), 0x0800_0002);
+
+        // BL #+0x4
+        let prefix = decode(0xF000);
+        let suffix = decode(0xF800);
+        assert_eq!(prefix.length(), 2);
+        assert_eq!(prefix.next_pc(0x0800_0000), 0x0800_0002);
+        assert_eq!(suffix.length(), 2);
+        assert_eq!(suffix.next_pc(0x0800_0002), 0x0800_0004);
+    }
+
+    #[test]
+    fn affects_flags() {
+        // ADD r0, r1, r2
+        assert_eq!(decode(0x1888).affects_flags(), FlagSet::NZCV);
+        // CMP r0, #0x1
+        assert_eq!(decode(0x2801).affects_flags(), FlagSet::NZCV);
+        // ADD r8, r0
+        assert_eq!(decode(0x4480).affects_flags(), FlagSet::NONE);
+        // AND r0, r1
+        assert_eq!(decode(0x4008).affects_flags(), FlagSet::NZ);
+        // PUSH {r4, lr}
+        assert_eq!(decode(0xB510).affects_flags(), FlagSet::NONE);
+    }
+
+    #[test]
+    fn disassemble_at_address() {
+        // B #+0x18
+        assert_eq!(disassemble(0xE00A), "B #+0x18");
+        assert_eq!(disassemble_at(0xE00A, 0x0800_0100), "B #0x08000118");
+        // BNE #-0x2
+        assert_eq!(disassemble_at(0xD1FD, 0x0800_0100), "BNE #0x080000FE");
+        // LDR r0, [pc, #0x8]
+        assert_eq!(disassemble(0x4802), "LDR r0, [pc, #0x8]");
+        assert_eq!(disassemble_at(0x4802, 0x0800_0100), "LDR r0, [#0x0800010C]");
+        assert_eq!(disassemble_at(0x4802, 0x0800_0102), "LDR r0, [#0x0800010C]");
+    }
+
+    #[test]
+    fn decode_eq() {
+        // ADD r0, r1, r2
+        assert_eq!(
+            decode(0x1888),
+            Instruction::AddSubtract {
+                subtract: false,
+                operand: AddSubOperand::Register(2),
+                rs: 1,
+                rd: 0,
+            }
+        );
+        // LDR r0, [r1, #0x4]
+        assert_eq!(
+            decode(0x6848),
+            Instruction::LoadStoreImmediate {
+                load: true,
+                byte: false,
+                offset: 1,
+                rb: 1,
+                rd: 0,
+            }
+        );
+        // PUSH {r4, lr}
+        assert_eq!(
+            decode(0xB510),
+            Instruction::PushPop {
+                pop: false,
+                pc_lr: true,
+                registers: 0x10,
+            }
+        );
+        // BNE #-0x2
+        assert_eq!(
+            decode(0xD1FD),
+            Instruction::ConditionalBranch {
+                cond: Cond::NE,
+                offset: -3,
+            }
+        );
+        assert_ne!(decode(0x1888), decode(0x1A88));
+    }
+
+    #[test]
+    fn display() {
+        let instr = decode(0x4802);
+        assert_eq!(format!("{instr}"), "LDR r0, [pc, #0x8]");
+        let instr = decode(0xD1FD);
+        assert_eq!(format!("{instr}"), "BNE #-0x2");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "low register index out of range")]
+    fn low_register_out_of_range() {
+        let _ = low_register(8);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "register index out of range")]
+    fn register_out_of_range() {
+        let _ = register(16);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ALU operation out of range")]
+    fn alu_op_out_of_range() {
+        let _ = alu_op(0x10);
+    }
+
+    #[test]
+    fn decode_reserved_conditions() {
+        // BEQ #+0x4
+        assert!(matches!(
+            decode(0xD000),
+            Instruction::ConditionalBranch {
+                cond: Cond::EQ,
+                offset: 0
+            }
+        ));
+        // Condition `AL`.
+        assert!(matches!(decode(0xDE00), Instruction::Undefined));
+        // SWI #0x12
+        assert!(matches!(
+            decode(0xDF12),
+            Instruction::SoftwareInterrupt { comment: 0x12 }
+        ));
+    }
+}
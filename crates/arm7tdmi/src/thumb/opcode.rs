@@ -0,0 +1,90 @@
+//! Field access on raw THUMB opcodes.
+
+use crate::bit::BitIndex;
+
+/// A raw THUMB opcode, providing access to the fields shared by many
+/// instruction formats.
+///
+/// ```text
+/// 15-11  10-8     7-6   5-3  2-0
+/// ...    Rn/Ro    ...   Rs   Rd
+/// ...    Rd (hi)  Imm (7-0)
+/// ```
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#thumbinstructionsummary>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct ThumbOpcode(pub u16);
+
+impl ThumbOpcode {
+    /// Returns the low destination register field (bits 2-0).
+    #[inline]
+    pub fn rd(self) -> u8 {
+        super::low_register(self.0.bits::<0, 3>())
+    }
+
+    /// Returns the source or base register field (bits 5-3).
+    #[inline]
+    pub fn rs(self) -> u8 {
+        super::low_register(self.0.bits::<3, 6>())
+    }
+
+    /// Returns the operand or offset register field (bits 8-6).
+    #[inline]
+    pub fn rn(self) -> u8 {
+        super::low_register(self.0.bits::<6, 9>())
+    }
+
+    /// Returns the destination register field of formats with an 8-bit
+    /// immediate (bits 10-8).
+    #[inline]
+    pub fn rd_hi(self) -> u8 {
+        super::low_register(self.0.bits::<8, 11>())
+    }
+
+    /// Returns the 8-bit immediate field (bits 7-0).
+    #[inline]
+    pub fn immediate(self) -> u8 {
+        self.0 as u8
+    }
+
+    /// Returns the 5-bit immediate offset field (bits 10-6).
+    #[inline]
+    pub fn offset5(self) -> u8 {
+        self.0.bits::<6, 11>() as u8
+    }
+}
+
+impl From<u16> for ThumbOpcode {
+    #[inline]
+    fn from(opcode: u16) -> ThumbOpcode {
+        ThumbOpcode(opcode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        // ADD r0, r1, r2
+        let opcode = ThumbOpcode(0x1888);
+        assert_eq!(opcode.rd(), 0);
+        assert_eq!(opcode.rs(), 1);
+        assert_eq!(opcode.rn(), 2);
+
+        // MOV r3, #0xAB
+        let opcode = ThumbOpcode(0x23AB);
+        assert_eq!(opcode.rd_hi(), 3);
+        assert_eq!(opcode.immediate(), 0xAB);
+
+        // LDR r1, [r2, #0x7C]
+        let opcode = ThumbOpcode(0x6FD1);
+        assert_eq!(opcode.offset5(), 0x1F);
+        assert_eq!(opcode.rs(), 2);
+        assert_eq!(opcode.rd(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+//! Checks the ARM and THUMB disassemblers against a corpus of opcodes and their
+//! expected disassembly.
+//!
+//! Branch targets are relative, as the opcodes are disassembled without an
+//! address.
+
+use arm7tdmi::{arm, thumb};
+
+/// ARM opcodes covering each instruction format.
+const ARM: &[(u32, &str)] = &[
+    (0xE12F_FF1E, "BX lr"),
+    (0xEA00_003E, "B #+0x100"),
+    (0xEBFF_FFFC, "BL #-0x8"),
+    (0x0001_0002, "ANDEQ r0, r1, r2"),
+    (0xE034_3185, "EORS r3, r4, r5, LSL #3"),
+    (0xE041_0332, "SUB r0, r1, r2, LSR r3"),
+    (0xE260_0000, "RSB r0, r0, #0x0"),
+    (0xE291_04FF, "ADDS r0, r1, #0xFF000000"),
+    (0xE0A1_0462, "ADC r0, r1, r2, ROR #8"),
+    (0xE350_0001, "CMP r0, #0x1"),
+    (0xE111_0FC2, "TST r1, r2, ASR #31"),
+    (0xE1A0_0061, "MOV r0, r1, RRX"),
+    (0xE3E0_0000, "MVN r0, #0x0"),
+    (0xE000_0291, "MUL r0, r1, r2"),
+    (0xE020_3291, "MLA r0, r1, r2, r3"),
+    (0xE081_0392, "UMULL r0, r1, r2, r3"),
+    (0xE0F1_0392, "SMLALS r0, r1, r2, r3"),
+    (0xE102_0091, "SWP r0, r1, [r2]"),
+    (0xE142_0091, "SWPB r0, r1, [r2]"),
+    (0xE10F_0000, "MRS r0, CPSR"),
+    (0xE129_F000, "MSR CPSR_fc, r0"),
+    (0xE368_F20F, "MSR SPSR_f, #0xF0000000"),
+    (0xE5B1_0004, "LDR r0, [r1, #0x4]!"),
+    (0xE641_0102, "STRB r0, [r1], -r2, LSL #2"),
+    (0xE151_00B2, "LDRH r0, [r1, #-0x2]"),
+    (0xE191_00D2, "LDRSB r0, [r1, r2]"),
+    (0xE0C1_00B6, "STRH r0, [r1], #0x6"),
+    (0xE8B0_000E, "LDMIA r0!, {r1, r2, r3}"),
+    (
+        0xE92D_4FF0,
+        "STMFD sp!, {r4, r5, r6, r7, r8, r9, r10, r11, lr}",
+    ),
+    (0xE8FD_8001, "LDMFD sp!, {r0, pc}^"),
+    (0xEF12_3456, "SWI #0x123456"),
+    (0xEE10_0F10, "MRC p15"),
+    (0xE7F0_00F0, "UND"),
+];
+
+/// THUMB opcodes covering each instruction format.
+const THUMB: &[(u16, &str)] = &[
+    (0x0108, "LSL r0, r1, #4"),
+    (0x081A, "LSR r2, r3, #32"),
+    (0x1040, "ASR r0, r0, #1"),
+    (0x1888, "ADD r0, r1, r2"),
+    (0x1FC8, "SUB r0, r1, #0x7"),
+    (0x20FF, "MOV r0, #0xFF"),
+    (0x290A, "CMP r1, #0xA"),
+    (0x3201, "ADD r2, #0x1"),
+    (0x3B80, "SUB r3, #0x80"),
+    (0x4008, "AND r0, r1"),
+    (0x4048, "EOR r0, r1"),
+    (0x4088, "LSL r0, r1"),
+    (0x41DA, "ROR r2, r3"),
+    (0x4208, "TST r0, r1"),
+    (0x4248, "NEG r0, r1"),
+    (0x42C8, "CMN r0, r1"),
+    (0x4348, "MUL r0, r1"),
+    (0x4388, "BIC r0, r1"),
+    (0x43C8, "MVN r0, r1"),
+    (0x4480, "ADD r8, r0"),
+    (0x4548, "CMP r0, r9"),
+    (0x46F7, "MOV pc, lr"),
+    (0x4770, "BX lr"),
+    (0x4802, "LDR r0, [pc, #0x8]"),
+    (0x5088, "STR r0, [r1, r2]"),
+    (0x5C88, "LDRB r0, [r1, r2]"),
+    (0x5288, "STRH r0, [r1, r2]"),
+    (0x5688, "LDSB r0, [r1, r2]"),
+    (0x5E88, "LDSH r0, [r1, r2]"),
+    (0x6848, "LDR r0, [r1, #0x4]"),
+    (0x77C8, "STRB r0, [r1, #0x1F]"),
+    (0x8FC8, "LDRH r0, [r1, #0x3E]"),
+    (0x90FF, "STR r0, [sp, #0x3FC]"),
+    (0xA004, "ADD r0, pc, #0x10"),
+    (0xA801, "ADD r0, sp, #0x4"),
+    (0xB082, "ADD sp, #-0x8"),
+    (0xB510, "PUSH {r4, lr}"),
+    (0xBD10, "POP {r4, pc}"),
+    (0xC006, "STMIA r0!, {r1, r2}"),
+    (0xC806, "LDMIA r0!, {r1, r2}"),
+    (0xD000, "BEQ #+0x4"),
+    (0xDF11, "SWI #0x11"),
+    (0xE7FC, "B #-0x4"),
+    (0xF000, "BL #+0x4 (prefix)"),
+    (0xF800, "BL lr, #0x0 (suffix)"),
+    (0xDE00, "UND"),
+];
+
+/// Panics listing every opcode whose disassembly differs from the expected
+/// output.
+fn check<T: Copy + std::fmt::UpperHex>(corpus: &[(T, &str)], disassemble: fn(T) -> String) {
+    let mismatches: Vec<String> = corpus
+        .iter()
+        .filter_map(|&(opcode, expected)| {
+            let actual = disassemble(opcode);
+            (actual != expected).then(|| {
+                format!(
+                    "{:#X}:\n  expected: {}\n    actual: {}",
+                    opcode, expected, actual
+                )
+            })
+        })
+        .collect();
+
+    if !mismatches.is_empty() {
+        panic!(
+            "{} of {} opcodes disassembled incorrectly:\n{}",
+            mismatches.len(),
+            corpus.len(),
+            mismatches.join("\n"),
+        );
+    }
+}
+
+#[test]
+fn arm_corpus() {
+    check(ARM, arm::disassemble);
+}
+
+#[test]
+fn thumb_corpus() {
+    check(THUMB, thumb::disassemble);
+}
@@ -0,0 +1,65 @@
+//! Runs the CPU test ROMs from [jsmolka/gba-tests].
+//!
+//! The ROMs run a sequence of tests, storing the number of the first failing
+//! test in `r12` (or `0` if all tests passed), before entering an infinite
+//! loop that branches to itself.
+//!
+//! The ROMs are in the `external/gba-tests` submodule, which must be checked
+//! out.
+//!
+//! [jsmolka/gba-tests]: https://github.com/jsmolka/gba-tests
+
+use std::fs;
+use std::path::Path;
+
+use arm7tdmi::test_util::{load_into, FlatMemory};
+use arm7tdmi::Cpu;
+
+/// Maximum number of instructions to execute before giving up.
+const BUDGET: u64 = 10_000_000;
+
+/// Runs the ROM at `path`, relative to the repository root, returning the
+/// number of the failing test, or `0` if all tests passed.
+fn run(path: &str) -> u32 {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(path);
+    let rom = fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "failed to read {}, is the gba-tests submodule checked out? {}",
+            path.display(),
+            err
+        )
+    });
+    let mut mem = FlatMemory::new();
+    load_into(&mut mem, 0x0800_0000, &rom);
+
+    // Start at the cartridge entry point, as the BIOS would.
+    let mut cpu = Cpu::new();
+    cpu.hle_boot(&mem, 0x0800_0000);
+
+    for _ in 0..BUDGET {
+        let pc = cpu.pc();
+        cpu.step(&mut mem);
+        if cpu.pc() == pc {
+            return cpu.reg(12);
+        }
+    }
+    panic!(
+        "{} did not finish within {} instructions",
+        path.display(),
+        BUDGET
+    );
+}
+
+#[test]
+fn arm() {
+    let failed = run("external/gba-tests/arm/arm.gba");
+    assert_eq!(failed, 0, "failed test {}", failed);
+}
+
+#[test]
+fn thumb() {
+    let failed = run("external/gba-tests/thumb/thumb.gba");
+    assert_eq!(failed, 0, "failed test {}", failed);
+}
@@ -0,0 +1,275 @@
+use alloc::boxed::Box;
+use alloc::vec;
+
+use crate::{SaveError, SaveType};
+
+/// The size of a block of EEPROM, the unit it is addressed in.
+const BLOCK_SIZE: usize = 8;
+/// The number of bits in a block.
+const BLOCK_BITS: u8 = 64;
+/// The number of dummy bits sent before the data of a read.
+const READ_DUMMY_BITS: u8 = 4;
+
+/// EEPROM backup memory, accessed through a bit-serial protocol.
+///
+/// Requests are sent one bit at a time, in bit 0 of halfwords written to the
+/// EEPROM (usually by DMA), most significant bit first:
+///
+/// ```text
+/// Read:  11 <address> 0
+/// Write: 10 <address> <64 data bits> 0
+/// ```
+///
+/// The address selects an 8 byte block, and is 6 bits for a 512 byte device,
+/// or 14 bits (of which the upper 4 are ignored) for an 8 KiB device. After a
+/// read request, 68 bits are read: 4 dummy bits followed by the 64 data bits.
+/// After a write, reading returns `1` when the device is ready, which is
+/// immediately.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartbackupeeprom>
+#[derive(Clone, Debug)]
+pub struct Eeprom {
+    data: Box<[u8]>,
+    address_bits: u8,
+    state: State,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    /// Waiting for the first bit of a request.
+    Idle,
+    /// Received the first bit of a request, waiting for the read/write bit.
+    Request,
+    /// Receiving the block address.
+    Address { read: bool, address: u16, bits: u8 },
+    /// Receiving the data of a write.
+    WriteData { address: u16, data: u64, bits: u8 },
+    /// Waiting for the end bit of a request.
+    End { read: bool, address: u16, data: u64 },
+    /// Sending the result of a read.
+    Reading { address: u16, bits: u8 },
+}
+
+impl Eeprom {
+    /// Creates an erased EEPROM for `save_type`, or `None` if it is not an
+    /// EEPROM type.
+    pub fn new(save_type: SaveType) -> Option<Eeprom> {
+        let address_bits = match save_type {
+            SaveType::Eeprom512 => 6,
+            SaveType::Eeprom8K => 14,
+            _ => return None,
+        };
+
+        Some(Eeprom {
+            data: vec![0xFF; save_type.size()].into_boxed_slice(),
+            address_bits,
+            state: State::Idle,
+        })
+    }
+
+    /// Creates an EEPROM for `save_type` with the contents of a save file.
+    ///
+    /// Fails if `save_type` is not an EEPROM type, or if `data` is not the size
+    /// of the EEPROM.
+    pub fn from_data(save_type: SaveType, data: &[u8]) -> Result<Eeprom, SaveError> {
+        let mut eeprom = Eeprom::new(save_type).ok_or(SaveError::NoBackup)?;
+        if data.len() != eeprom.data.len() {
+            return Err(SaveError::SizeMismatch {
+                expected: eeprom.data.len(),
+                actual: data.len(),
+            });
+        }
+
+        eeprom.data.copy_from_slice(data);
+        Ok(eeprom)
+    }
+
+    /// Returns the contents of the EEPROM.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the byte offset of the block at `address`, wrapping addresses
+    /// past the end of the device.
+    fn block_offset(&self, address: u16) -> usize {
+        let blocks = self.data.len() / BLOCK_SIZE;
+        (usize::from(address) & (blocks - 1)) * BLOCK_SIZE
+    }
+
+    /// Writes a bit of a request.
+    pub fn write_bit(&mut self, bit: bool) {
+        self.state = match self.state {
+            State::Idle | State::Reading { .. } if bit => State::Request,
+            State::Idle | State::Reading { .. } => State::Idle,
+            State::Request => State::Address {
+                read: bit,
+                address: 0,
+                bits: 0,
+            },
+            State::Address {
+                read,
+                address,
+                bits,
+            } => {
+                let address = (address << 1) | u16::from(bit);
+                match bits + 1 {
+                    bits if bits < self.address_bits => State::Address {
+                        read,
+                        address,
+                        bits,
+                    },
+                    _ if read => State::End {
+                        read,
+                        address,
+                        data: 0,
+                    },
+                    _ => State::WriteData {
+                        address,
+                        data: 0,
+                        bits: 0,
+                    },
+                }
+            }
+            State::WriteData {
+                address,
+                data,
+                bits,
+            } => {
+                let data = (data << 1) | u64::from(bit);
+                match bits + 1 {
+                    BLOCK_BITS => State::End {
+                        read: false,
+                        address,
+                        data,
+                    },
+                    bits => State::WriteData {
+                        address,
+                        data,
+                        bits,
+                    },
+                }
+            }
+            State::End {
+                read,
+                address,
+                data,
+            } => {
+                if read {
+                    State::Reading { address, bits: 0 }
+                } else {
+                    let offset = self.block_offset(address);
+                    self.data[offset..offset + BLOCK_SIZE].copy_from_slice(&data.to_be_bytes());
+                    State::Idle
+                }
+            }
+        };
+    }
+
+    /// Reads a bit of the result of a request.
+    pub fn read_bit(&mut self) -> bool {
+        match self.state {
+            State::Reading { address, bits } => {
+                let bit = match bits.checked_sub(READ_DUMMY_BITS) {
+                    Some(i) => {
+                        let byte = self.data[self.block_offset(address) + usize::from(i / 8)];
+                        byte & (0x80 >> (i % 8)) != 0
+                    }
+                    None => false,
+                };
+
+                self.state = match bits + 1 {
+                    bits if bits < READ_DUMMY_BITS + BLOCK_BITS => State::Reading { address, bits },
+                    _ => State::Idle,
+                };
+                bit
+            }
+            // Writes complete immediately, so the device is always ready.
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_bits(eeprom: &mut Eeprom, value: u64, len: u8) {
+        for i in (0..len).rev() {
+            eeprom.write_bit(value & (1 << i) != 0);
+        }
+    }
+
+    fn write(eeprom: &mut Eeprom, address: u16, data: u64) {
+        write_bits(eeprom, 0b10, 2);
+        write_bits(eeprom, address.into(), eeprom.address_bits);
+        write_bits(eeprom, data, 64);
+        write_bits(eeprom, 0, 1);
+    }
+
+    fn read(eeprom: &mut Eeprom, address: u16) -> u64 {
+        write_bits(eeprom, 0b11, 2);
+        write_bits(eeprom, address.into(), eeprom.address_bits);
+        write_bits(eeprom, 0, 1);
+
+        for _ in 0..4 {
+            assert!(!eeprom.read_bit());
+        }
+        (0..64).fold(0, |data, _| (data << 1) | u64::from(eeprom.read_bit()))
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(Eeprom::new(SaveType::Eeprom512).unwrap().data().len(), 512);
+        assert_eq!(Eeprom::new(SaveType::Eeprom8K).unwrap().data().len(), 8192);
+        assert!(Eeprom::new(SaveType::Sram).is_none());
+    }
+
+    #[test]
+    fn from_data() {
+        let mut data = vec![0xFF; 512];
+        data[8..16].copy_from_slice(&0x1122_3344_5566_7788u64.to_be_bytes());
+        let mut eeprom = Eeprom::from_data(SaveType::Eeprom512, &data).unwrap();
+        assert_eq!(read(&mut eeprom, 1), 0x1122_3344_5566_7788);
+
+        assert!(matches!(
+            Eeprom::from_data(SaveType::Eeprom8K, &data),
+            Err(SaveError::SizeMismatch {
+                expected: 8192,
+                actual: 512
+            })
+        ));
+        assert!(matches!(
+            Eeprom::from_data(SaveType::Sram, &data),
+            Err(SaveError::NoBackup)
+        ));
+    }
+
+    #[test]
+    fn write_read_512() {
+        let mut eeprom = Eeprom::new(SaveType::Eeprom512).unwrap();
+
+        write(&mut eeprom, 0x3F, 0x0123_4567_89AB_CDEF);
+        assert!(eeprom.read_bit());
+        assert_eq!(read(&mut eeprom, 0x3F), 0x0123_4567_89AB_CDEF);
+        assert_eq!(read(&mut eeprom, 0x00), u64::MAX);
+        assert_eq!(
+            eeprom.data()[0x1F8..],
+            0x0123_4567_89AB_CDEFu64.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn write_read_8k() {
+        let mut eeprom = Eeprom::new(SaveType::Eeprom8K).unwrap();
+
+        write(&mut eeprom, 0x3FF, 0xDEAD_BEEF_0000_FFFF);
+        write(&mut eeprom, 0x001, 0x1122_3344_5566_7788);
+        assert_eq!(read(&mut eeprom, 0x3FF), 0xDEAD_BEEF_0000_FFFF);
+        assert_eq!(read(&mut eeprom, 0x001), 0x1122_3344_5566_7788);
+        // The upper 4 address bits are ignored.
+        assert_eq!(read(&mut eeprom, 0x3C01), 0x1122_3344_5566_7788);
+        assert_eq!(eeprom.data()[8..16], 0x1122_3344_5566_7788u64.to_be_bytes());
+    }
+}
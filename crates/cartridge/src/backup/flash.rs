@@ -0,0 +1,319 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use core::mem;
+
+use crate::{SaveError, SaveType};
+
+/// The size of a bank of Flash, the range addressable at once.
+const BANK_SIZE: usize = 0x10000;
+/// The size of an erasable sector.
+const SECTOR_SIZE: usize = 0x1000;
+
+/// The addresses written to unlock a command.
+const UNLOCK_ADDRESS_1: u16 = 0x5555;
+const UNLOCK_ADDRESS_2: u16 = 0x2AAA;
+
+/// Manufacturer and device ID of the Panasonic MN63F805MNP (64 KiB).
+const ID_64K: (u8, u8) = (0x32, 0x1B);
+/// Manufacturer and device ID of the Sanyo LE26FV10N1TS (128 KiB).
+const ID_128K: (u8, u8) = (0x62, 0x13);
+
+/// Flash backup memory, controlled by command sequences.
+///
+/// Each command is unlocked by writing `0xAA` to `0x5555` and `0x55` to
+/// `0x2AAA`, followed by the command byte:
+///
+/// ```text
+/// 0x90 to 0x5555  Enter chip ID mode, the IDs are read from 0x0000 and 0x0001
+/// 0xF0 to 0x5555  Exit chip ID mode
+/// 0x80 to 0x5555  Prepare erase, followed by an unlocked erase command
+///   0x10 to 0x5555  Erase the entire chip
+///   0x30 to 0xn000  Erase the 4 KiB sector n
+/// 0xA0 to 0x5555  Program the byte written next
+/// 0xB0 to 0x5555  Select the bank written next to 0x0000 (128 KiB only)
+/// ```
+///
+/// A 128 KiB device has two 64 KiB banks, only one of which is mapped at a
+/// time.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartbackupflashrom>
+#[derive(Clone, Debug)]
+pub struct Flash {
+    data: Box<[u8]>,
+    id: (u8, u8),
+    bank: usize,
+    unlock: Unlock,
+    mode: Mode,
+    id_mode: bool,
+}
+
+/// Progress through the unlock sequence of a command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Unlock {
+    None,
+    First,
+    Second,
+}
+
+/// The operation prepared by the last command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    Normal,
+    Erase,
+    Program,
+    BankSelect,
+}
+
+impl Flash {
+    /// Creates an erased Flash for `save_type`, or `None` if it is not a Flash
+    /// type.
+    pub fn new(save_type: SaveType) -> Option<Flash> {
+        let id = match save_type {
+            SaveType::Flash64K => ID_64K,
+            SaveType::Flash128K => ID_128K,
+            _ => return None,
+        };
+
+        Some(Flash {
+            data: vec![0xFF; save_type.size()].into_boxed_slice(),
+            id,
+            bank: 0,
+            unlock: Unlock::None,
+            mode: Mode::Normal,
+            id_mode: false,
+        })
+    }
+
+    /// Returns the manufacturer and device ID of the chip.
+    pub fn id(&self) -> (u8, u8) {
+        self.id
+    }
+
+    /// Creates a Flash for `save_type` with the contents of a save file.
+    ///
+    /// Fails if `save_type` is not a Flash type, or if `data` is not the size
+    /// of the Flash.
+    pub fn from_data(save_type: SaveType, data: &[u8]) -> Result<Flash, SaveError> {
+        let mut flash = Flash::new(save_type).ok_or(SaveError::NoBackup)?;
+        if data.len() != flash.data.len() {
+            return Err(SaveError::SizeMismatch {
+                expected: flash.data.len(),
+                actual: data.len(),
+            });
+        }
+
+        flash.data.copy_from_slice(data);
+        Ok(flash)
+    }
+
+    /// Returns the contents of the Flash.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the offset into the data of `addr` in the current bank.
+    fn offset(&self, addr: u16) -> usize {
+        self.bank * BANK_SIZE + usize::from(addr)
+    }
+
+    /// Reads a byte at `addr`.
+    pub fn read8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000 if self.id_mode => self.id.0,
+            0x0001 if self.id_mode => self.id.1,
+            addr => self.data[self.offset(addr)],
+        }
+    }
+
+    /// Writes a byte at `addr`, as part of a command sequence.
+    pub fn write8(&mut self, addr: u16, value: u8) {
+        match self.mode {
+            Mode::Program => {
+                // Programming can only clear bits, erasing sets them.
+                let offset = self.offset(addr);
+                self.data[offset] &= value;
+                self.mode = Mode::Normal;
+                return;
+            }
+            Mode::BankSelect if addr == 0x0000 => {
+                self.bank = usize::from(value & 1);
+                self.mode = Mode::Normal;
+                return;
+            }
+            _ => {}
+        }
+
+        self.unlock = match (self.unlock, addr, value) {
+            (_, UNLOCK_ADDRESS_1, 0xAA) => Unlock::First,
+            (Unlock::First, UNLOCK_ADDRESS_2, 0x55) => Unlock::Second,
+            (Unlock::Second, addr, command) => {
+                self.command(addr, command);
+                Unlock::None
+            }
+            _ => Unlock::None,
+        };
+    }
+
+    /// Executes an unlocked command.
+    ///
+    /// Any command other than an erase following the erase command (`0x80`)
+    /// cancels the erase.
+    fn command(&mut self, addr: u16, command: u8) {
+        let mode = mem::replace(&mut self.mode, Mode::Normal);
+        match (mode, addr, command) {
+            (Mode::Erase, UNLOCK_ADDRESS_1, 0x10) => self.data.fill(0xFF),
+            (Mode::Erase, addr, 0x30) => {
+                let start = self.offset(addr) & !(SECTOR_SIZE - 1);
+                self.data[start..start + SECTOR_SIZE].fill(0xFF);
+            }
+            (_, UNLOCK_ADDRESS_1, 0x90) => self.id_mode = true,
+            (_, UNLOCK_ADDRESS_1, 0xF0) => self.id_mode = false,
+            (_, UNLOCK_ADDRESS_1, 0x80) => self.mode = Mode::Erase,
+            (_, UNLOCK_ADDRESS_1, 0xA0) => self.mode = Mode::Program,
+            (_, UNLOCK_ADDRESS_1, 0xB0) if self.data.len() > BANK_SIZE => {
+                self.mode = Mode::BankSelect
+            }
+            (_, addr, command) => {
+                log::warn!("unknown flash command {:#04X} at {:#06X}", command, addr);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(flash: &mut Flash, addr: u16, command: u8) {
+        flash.write8(UNLOCK_ADDRESS_1, 0xAA);
+        flash.write8(UNLOCK_ADDRESS_2, 0x55);
+        flash.write8(addr, command);
+    }
+
+    fn program(flash: &mut Flash, addr: u16, value: u8) {
+        command(flash, UNLOCK_ADDRESS_1, 0xA0);
+        flash.write8(addr, value);
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(
+            Flash::new(SaveType::Flash64K).unwrap().data().len(),
+            0x10000
+        );
+        assert_eq!(
+            Flash::new(SaveType::Flash128K).unwrap().data().len(),
+            0x20000
+        );
+        assert!(Flash::new(SaveType::Eeprom8K).is_none());
+    }
+
+    #[test]
+    fn from_data() {
+        let mut data = vec![0xFF; 0x10000];
+        data[0x1234] = 0x56;
+        let flash = Flash::from_data(SaveType::Flash64K, &data).unwrap();
+        assert_eq!(flash.read8(0x1234), 0x56);
+
+        assert!(matches!(
+            Flash::from_data(SaveType::Flash128K, &data),
+            Err(SaveError::SizeMismatch {
+                expected: 0x20000,
+                actual: 0x10000
+            })
+        ));
+        assert!(matches!(
+            Flash::from_data(SaveType::Eeprom512, &data),
+            Err(SaveError::NoBackup)
+        ));
+    }
+
+    #[test]
+    fn chip_id() {
+        let mut flash = Flash::new(SaveType::Flash128K).unwrap();
+        assert_eq!(flash.read8(0x0000), 0xFF);
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x90);
+        assert_eq!((flash.read8(0x0000), flash.read8(0x0001)), (0x62, 0x13));
+        assert_eq!(flash.id(), (0x62, 0x13));
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0xF0);
+        assert_eq!(flash.read8(0x0000), 0xFF);
+    }
+
+    #[test]
+    fn sector_erase() {
+        let mut flash = Flash::new(SaveType::Flash64K).unwrap();
+        program(&mut flash, 0x1000, 0x12);
+        program(&mut flash, 0x1FFF, 0x34);
+        program(&mut flash, 0x2000, 0x56);
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x80);
+        command(&mut flash, 0x1000, 0x30);
+        assert_eq!(flash.read8(0x1000), 0xFF);
+        assert_eq!(flash.read8(0x1FFF), 0xFF);
+        assert_eq!(flash.read8(0x2000), 0x56);
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x80);
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x10);
+        assert_eq!(flash.read8(0x2000), 0xFF);
+    }
+
+    #[test]
+    fn program_clears_bits() {
+        let mut flash = Flash::new(SaveType::Flash64K).unwrap();
+        program(&mut flash, 0x0123, 0xF0);
+        program(&mut flash, 0x0123, 0x3C);
+        assert_eq!(flash.read8(0x0123), 0x30);
+    }
+
+    #[test]
+    fn erase_cancelled() {
+        let mut flash = Flash::new(SaveType::Flash64K).unwrap();
+        program(&mut flash, 0x1000, 0x12);
+
+        // An ID command after the erase command cancels the erase.
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x80);
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x90);
+        command(&mut flash, UNLOCK_ADDRESS_1, 0xF0);
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x10);
+        assert_eq!(flash.read8(0x1000), 0x12);
+
+        // As does a program command.
+        command(&mut flash, UNLOCK_ADDRESS_1, 0x80);
+        program(&mut flash, 0x2000, 0x34);
+        command(&mut flash, 0x1000, 0x30);
+        assert_eq!(flash.read8(0x1000), 0x12);
+        assert_eq!(flash.read8(0x2000), 0x34);
+    }
+
+    #[test]
+    fn program_banks() {
+        let mut flash = Flash::new(SaveType::Flash128K).unwrap();
+        program(&mut flash, 0x0123, 0xAB);
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0xB0);
+        flash.write8(0x0000, 1);
+        assert_eq!(flash.read8(0x0123), 0xFF);
+        program(&mut flash, 0x0123, 0xCD);
+        assert_eq!(flash.read8(0x0123), 0xCD);
+
+        command(&mut flash, UNLOCK_ADDRESS_1, 0xB0);
+        flash.write8(0x0000, 0);
+        assert_eq!(flash.read8(0x0123), 0xAB);
+
+        assert_eq!(flash.data()[0x0123], 0xAB);
+        assert_eq!(flash.data()[0x10123], 0xCD);
+    }
+
+    #[test]
+    fn no_banks_64k() {
+        let mut flash = Flash::new(SaveType::Flash64K).unwrap();
+        command(&mut flash, UNLOCK_ADDRESS_1, 0xB0);
+        flash.write8(0x0000, 1);
+        program(&mut flash, 0x0123, 0xAB);
+        assert_eq!(flash.data()[0x0123], 0xAB);
+    }
+}
@@ -0,0 +1,278 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::strings::strings;
+use crate::Cartridge;
+
+mod eeprom;
+mod flash;
+
+pub use self::eeprom::Eeprom;
+pub use self::flash::Flash;
+
+/// An error loading save data.
+#[derive(Clone, Copy, Debug)]
+pub enum SaveError {
+    /// The cartridge has no backup memory of the required type.
+    NoBackup,
+    /// The save data is not the size of the backup memory.
+    SizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SaveError::NoBackup => f.write_str("cartridge has no backup memory"),
+            SaveError::SizeMismatch { expected, actual } => write!(
+                f,
+                "save data is {} bytes, expected {} bytes",
+                actual, expected,
+            ),
+        }
+    }
+}
+
+impl core::error::Error for SaveError {}
+
+/// The type of backup memory used by a cartridge to store save data.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartbackupids>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SaveType {
+    /// No backup memory.
+    None,
+    /// 512 byte EEPROM.
+    Eeprom512,
+    /// 8 KiB EEPROM.
+    Eeprom8K,
+    /// 32 KiB SRAM (or FRAM).
+    Sram,
+    /// 64 KiB Flash.
+    Flash64K,
+    /// 128 KiB Flash.
+    Flash128K,
+}
+
+impl SaveType {
+    /// The ID strings embedded in the ROM by the library used to access each
+    /// type of backup memory.
+    ///
+    /// The size of an EEPROM cannot be determined from the ROM, so `EEPROM_V`
+    /// maps to the larger 8 KiB device.
+    ///
+    /// # Sources
+    ///
+    /// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartbackupids>
+    pub const MARKERS: &'static [(&'static str, SaveType)] = &[
+        ("EEPROM_V", SaveType::Eeprom8K),
+        ("SRAM_V", SaveType::Sram),
+        ("SRAM_F_V", SaveType::Sram),
+        ("FLASH_V", SaveType::Flash64K),
+        ("FLASH512_V", SaveType::Flash64K),
+        ("FLASH1M_V", SaveType::Flash128K),
+    ];
+
+    /// Returns the size of the backup memory in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            SaveType::None => 0,
+            SaveType::Eeprom512 => 0x200,
+            SaveType::Eeprom8K => 0x2000,
+            SaveType::Sram => 0x8000,
+            SaveType::Flash64K => 0x10000,
+            SaveType::Flash128K => 0x20000,
+        }
+    }
+
+    /// Returns erased backup memory of this type.
+    pub(crate) fn blank(self) -> Box<[u8]> {
+        vec![0xFF; self.size()].into_boxed_slice()
+    }
+}
+
+/// Detects the type of backup memory from the ID string embedded in the ROM
+/// by the library used to access it, see [`SaveType::MARKERS`].
+pub(crate) fn detect_save_type(rom: &[u8]) -> SaveType {
+    let min_len = SaveType::MARKERS
+        .iter()
+        .map(|(marker, _)| marker.len())
+        .min();
+
+    for (offset, s) in strings(rom, min_len.unwrap_or(0)) {
+        // ID strings are word aligned.
+        let aligned = (offset.wrapping_neg() & 0b11..s.len()).step_by(4);
+        for s in aligned.map(|i| &s[i..]) {
+            for &(marker, save_type) in SaveType::MARKERS {
+                if s.starts_with(marker) {
+                    return save_type;
+                }
+            }
+        }
+    }
+
+    SaveType::None
+}
+
+impl Cartridge {
+    /// Returns the type of backup memory used by the cartridge.
+    pub fn save_type(&self) -> SaveType {
+        self.save_type
+    }
+
+    /// Returns the size of the cartridge's backup memory in bytes.
+    pub fn save_size(&self) -> usize {
+        self.save_type.size()
+    }
+
+    /// Loads the contents of the backup memory from a save file.
+    ///
+    /// The save file must be exactly the size of the detected backup memory,
+    /// otherwise the backup memory is left unchanged. As the size of an EEPROM
+    /// cannot be determined from the ROM, an EEPROM save may be either 512
+    /// bytes or 8 KiB, and the save type is set from its size.
+    ///
+    /// The contents can be used to create an emulated backup device with
+    /// [`Eeprom::from_data`] or [`Flash::from_data`].
+    pub fn load_save(&mut self, bytes: &[u8]) -> Result<(), SaveError> {
+        let save_type = match (self.save_type, bytes.len()) {
+            (SaveType::None, _) => return Err(SaveError::NoBackup),
+            (SaveType::Eeprom512 | SaveType::Eeprom8K, 0x200) => SaveType::Eeprom512,
+            (SaveType::Eeprom512 | SaveType::Eeprom8K, 0x2000) => SaveType::Eeprom8K,
+            (save_type, len) if len == save_type.size() => save_type,
+            (save_type, len) => {
+                return Err(SaveError::SizeMismatch {
+                    expected: save_type.size(),
+                    actual: len,
+                })
+            }
+        };
+
+        self.save_type = save_type;
+        self.save = bytes.into();
+        Ok(())
+    }
+
+    /// Returns the contents of the backup memory, to be written to a save file.
+    ///
+    /// These are the contents last loaded with [`Cartridge::load_save`], or
+    /// erased backup memory. Writes to an [`Eeprom`] or [`Flash`] device
+    /// created from them are not reflected here, save [`Eeprom::data`] or
+    /// [`Flash::data`] instead.
+    pub fn save_bytes(&self) -> Vec<u8> {
+        self.save.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    fn with_marker(marker: &str) -> Cartridge {
+        let mut rom = ROM.to_vec();
+        rom.resize(0x400, 0);
+        rom[0x200..0x200 + marker.len()].copy_from_slice(marker.as_bytes());
+        Cartridge::load_from_bytes(rom).unwrap()
+    }
+
+    #[test]
+    fn detect() {
+        let cases = [
+            ("EEPROM_V124", SaveType::Eeprom8K, 0x2000),
+            ("SRAM_V113", SaveType::Sram, 0x8000),
+            ("SRAM_F_V100", SaveType::Sram, 0x8000),
+            ("FLASH_V121", SaveType::Flash64K, 0x10000),
+            ("FLASH512_V131", SaveType::Flash64K, 0x10000),
+            ("FLASH1M_V103", SaveType::Flash128K, 0x20000),
+        ];
+
+        for (marker, save_type, size) in cases {
+            let cart = with_marker(marker);
+            assert_eq!(cart.save_type(), save_type, "{}", marker);
+            assert_eq!(cart.save_size(), size, "{}", marker);
+        }
+    }
+
+    #[test]
+    fn markers() {
+        for &(marker, save_type) in SaveType::MARKERS {
+            assert_eq!(with_marker(marker).save_type(), save_type, "{}", marker);
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(SaveType::None.size(), 0);
+        assert_eq!(SaveType::Eeprom512.size(), 512);
+        assert_eq!(SaveType::Eeprom8K.size(), 8192);
+        assert_eq!(SaveType::Sram.size(), 32768);
+        assert_eq!(SaveType::Flash64K.size(), 65536);
+        assert_eq!(SaveType::Flash128K.size(), 131072);
+    }
+
+    #[test]
+    fn load_save() {
+        let mut cart = with_marker("SRAM_V113");
+        assert_eq!(cart.save_bytes(), vec![0xFF; 0x8000]);
+
+        let save: Vec<u8> = (0..0x8000).map(|i| i as u8).collect();
+        cart.load_save(&save).unwrap();
+        assert_eq!(cart.save_bytes(), save);
+    }
+
+    #[test]
+    fn load_save_wrong_size() {
+        let mut cart = with_marker("SRAM_V113");
+        assert!(matches!(
+            cart.load_save(&[0; 0x2000]),
+            Err(SaveError::SizeMismatch {
+                expected: 0x8000,
+                actual: 0x2000,
+            })
+        ));
+        assert_eq!(cart.save_bytes(), vec![0xFF; 0x8000]);
+
+        let mut rom = ROM.to_vec();
+        rom.resize(0x400, 0);
+        let mut cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert!(matches!(cart.load_save(&[]), Err(SaveError::NoBackup)));
+    }
+
+    #[test]
+    fn load_save_eeprom() {
+        let mut cart = with_marker("EEPROM_V124");
+        assert_eq!(cart.save_type(), SaveType::Eeprom8K);
+
+        let save: Vec<u8> = (0..0x200).map(|i| i as u8).collect();
+        cart.load_save(&save).unwrap();
+        assert_eq!(cart.save_type(), SaveType::Eeprom512);
+        assert_eq!(cart.save_bytes(), save);
+
+        let save = vec![0; 0x2000];
+        cart.load_save(&save).unwrap();
+        assert_eq!(cart.save_type(), SaveType::Eeprom8K);
+        assert_eq!(cart.save_bytes(), save);
+
+        assert!(matches!(
+            cart.load_save(&[0; 0x400]),
+            Err(SaveError::SizeMismatch {
+                expected: 0x2000,
+                actual: 0x400,
+            })
+        ));
+    }
+
+    #[test]
+    fn unaligned_marker() {
+        let mut rom = ROM.to_vec();
+        rom.resize(0x400, 0);
+        rom[0x201..0x208].copy_from_slice(b"SRAM_V1");
+
+        assert_eq!(detect_save_type(&rom), SaveType::None);
+    }
+}
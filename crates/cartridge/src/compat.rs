@@ -0,0 +1,162 @@
+use crate::header::{FIXED_VALUE, FIXED_VALUE_OFFSET, LOGO_RANGE, NINTENDO_LOGO};
+use crate::{Cartridge, CartridgeHeader};
+
+/// Destination region and language, from the last character of the game code.
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartridgeheader>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Region {
+    /// Japan (`J`).
+    Japan,
+    /// USA/English (`E`).
+    Usa,
+    /// Europe/Elsewhere (`P`).
+    Europe,
+    /// German (`D`).
+    German,
+    /// French (`F`).
+    French,
+    /// Italian (`I`).
+    Italian,
+    /// Spanish (`S`).
+    Spanish,
+    /// An unrecognised region character.
+    Unknown(u8),
+}
+
+impl Region {
+    /// Returns the region for a region character.
+    pub fn from_code(code: u8) -> Region {
+        match code {
+            b'J' => Region::Japan,
+            b'E' => Region::Usa,
+            b'P' => Region::Europe,
+            b'D' => Region::German,
+            b'F' => Region::French,
+            b'I' => Region::Italian,
+            b'S' => Region::Spanish,
+            code => Region::Unknown(code),
+        }
+    }
+}
+
+impl CartridgeHeader {
+    /// Returns the destination region of the game.
+    pub fn region(&self) -> Region {
+        Region::from_code(self.game_code.buf[3])
+    }
+}
+
+/// A summary of whether a cartridge would be accepted by the BIOS.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CompatReport {
+    /// Destination region of the game.
+    pub region: Region,
+    /// Whether the Nintendo logo matches the one in the BIOS.
+    pub logo_valid: bool,
+    /// Whether the fixed value at `0xB2` is `0x96`.
+    pub fixed_value_valid: bool,
+    /// Whether the header checksum is correct.
+    pub checksum_valid: bool,
+}
+
+impl CompatReport {
+    /// Checks if the BIOS would boot the cartridge.
+    pub fn bootable(&self) -> bool {
+        self.logo_valid && self.fixed_value_valid && self.checksum_valid
+    }
+}
+
+impl Cartridge {
+    /// Checks the parts of the header verified by the BIOS on boot.
+    pub fn compatibility(&self) -> CompatReport {
+        let rom = &self.rom;
+
+        CompatReport {
+            region: self.header.region(),
+            logo_valid: rom[LOGO_RANGE] == NINTENDO_LOGO,
+            fixed_value_valid: rom[FIXED_VALUE_OFFSET] == FIXED_VALUE,
+            checksum_valid: self.checksum_valid(),
+        }
+    }
+
+    /// Returns the header checksum computed from the ROM.
+    pub fn computed_checksum(&self) -> u8 {
+        match CartridgeHeader::computed_checksum(&self.rom) {
+            Some(checksum) => checksum,
+            None => unreachable!("loaded ROM contains a header"),
+        }
+    }
+
+    /// Checks if the header checksum stored in the ROM is correct.
+    pub fn checksum_valid(&self) -> bool {
+        CartridgeHeader::checksum_valid(&self.rom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    #[test]
+    fn valid() {
+        let cart = Cartridge::load_from_bytes(ROM).unwrap();
+        let report = cart.compatibility();
+
+        assert_eq!(
+            report,
+            CompatReport {
+                region: Region::Unknown(b'7'),
+                logo_valid: true,
+                fixed_value_valid: true,
+                checksum_valid: true,
+            }
+        );
+        assert!(report.bootable());
+    }
+
+    #[test]
+    fn tampered_logo() {
+        let mut rom = ROM.to_vec();
+        rom[LOGO_RANGE.start] ^= 0xFF;
+
+        let report = Cartridge::load_from_bytes(rom).unwrap().compatibility();
+        assert!(!report.logo_valid);
+        assert!(report.checksum_valid);
+        assert!(!report.bootable());
+    }
+
+    #[test]
+    fn checksum() {
+        let cart = Cartridge::load_from_bytes(ROM).unwrap();
+        assert!(cart.checksum_valid());
+        assert_eq!(cart.computed_checksum(), cart.header.checksum);
+        assert!(CartridgeHeader::checksum_valid(ROM));
+
+        let mut rom = ROM.to_vec();
+        rom[0xA0] ^= 0xFF;
+        assert!(!CartridgeHeader::checksum_valid(&rom));
+
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert!(!cart.checksum_valid());
+        assert_ne!(cart.computed_checksum(), cart.header.checksum);
+        assert!(!cart.compatibility().checksum_valid);
+    }
+
+    #[test]
+    fn short_header() {
+        assert_eq!(CartridgeHeader::computed_checksum(&ROM[..0xBC]), None);
+        assert!(!CartridgeHeader::checksum_valid(&ROM[..0xBC]));
+        assert!(!CartridgeHeader::checksum_valid(&ROM[..0xBD]));
+    }
+
+    #[test]
+    fn region() {
+        let header = CartridgeHeader::new("HOMEBREW", "BHBP", "01", 0).unwrap();
+        assert_eq!(header.region(), Region::Europe);
+    }
+}
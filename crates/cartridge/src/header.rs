@@ -1,6 +1,8 @@
-use std::ops::Range;
+use core::fmt;
+use core::ops::Range;
 
 use crate::util::ascii::Ascii;
+use crate::Cartridge;
 
 /// GBA cartridge header.
 ///
@@ -54,34 +56,118 @@ pub struct CartridgeHeader {
 }
 
 /// ROMs must have 192 bytes at a minimum.
-const HEADER_MIN_SIZE: usize = 0xC0;
+pub(crate) const HEADER_MIN_SIZE: usize = 0xC0;
 
-const CHECKSUM_OFFSET: usize = 0xBD;
+const ENTRY_POINT_OFFSET: usize = 0x00;
+pub(crate) const FIXED_VALUE_OFFSET: usize = 0xB2;
+pub(crate) const CHECKSUM_OFFSET: usize = 0xBD;
 const SOFTWARE_VERSION_OFFSET: usize = 0xBC;
 
-const CHECKSUM_RANGE: Range<usize> = 0xA0..0xBD;
+pub(crate) const LOGO_RANGE: Range<usize> = 0x04..0xA0;
+
+pub(crate) const CHECKSUM_RANGE: Range<usize> = 0xA0..0xBD;
 const GAME_TITLE_RANGE: Range<usize> = 0xA0..0xAC;
 const GAME_CODE_RANGE: Range<usize> = 0xAC..0xB0;
 const MAKER_CODE_RANGE: Range<usize> = 0xB0..0xB2;
 
-/// An error when a ROM header is incomplete.
-#[derive(Clone, Copy, Debug, thiserror::Error)]
+/// Fixed value at `0xB2`.
+pub(crate) const FIXED_VALUE: u8 = 0x96;
+
+/// Entry point written by [`CartridgeHeader::to_bytes`], `B 0xC0`, branching
+/// to the first instruction following the header.
+const ENTRY_POINT: u32 = 0xEA00_002E;
+
+/// Compressed Nintendo logo bitmap, verified by the BIOS on boot.
+pub(crate) const NINTENDO_LOGO: [u8; 156] = [
+    0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
+    0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21, 0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20,
+    0x10, 0x46, 0x4A, 0x4A, 0xF8, 0x27, 0x31, 0xEC, 0x58, 0xC7, 0xE8, 0x33, 0x82, 0xE3, 0xCE, 0xBF,
+    0x85, 0xF4, 0xDF, 0x94, 0xCE, 0x4B, 0x09, 0xC1, 0x94, 0x56, 0x8A, 0xC0, 0x13, 0x72, 0xA7, 0xFC,
+    0x9F, 0x84, 0x4D, 0x73, 0xA3, 0xCA, 0x9A, 0x61, 0x58, 0x97, 0xA3, 0x27, 0xFC, 0x03, 0x98, 0x76,
+    0x23, 0x1D, 0xC7, 0x61, 0x03, 0x04, 0xAE, 0x56, 0xBF, 0x38, 0x84, 0x00, 0x40, 0xA7, 0x0E, 0xFD,
+    0xFF, 0x52, 0xFE, 0x03, 0x6F, 0x95, 0x30, 0xF1, 0x97, 0xFB, 0xC0, 0x85, 0x60, 0xD6, 0x80, 0x25,
+    0xA9, 0x63, 0xBE, 0x03, 0x01, 0x4E, 0x38, 0xE2, 0xF9, 0xA2, 0x34, 0xFF, 0xBB, 0x3E, 0x03, 0x44,
+    0x78, 0x00, 0x90, 0xCB, 0x88, 0x11, 0x3A, 0x94, 0x65, 0xC0, 0x7C, 0x63, 0x87, 0xF0, 0x3C, 0xAF,
+    0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
+];
+
+/// An error in a ROM header.
+#[derive(Clone, Copy, Debug)]
 pub enum HeaderError {
-    #[error("incomplete ROM header")]
     IncompleteHeader,
+    InvalidField {
+        /// Name of the field.
+        field: &'static str,
+    },
+    InvalidChecksum {
+        /// Checksum stored in the header.
+        expected: u8,
+        /// Checksum computed from the header.
+        computed: u8,
+    },
+    InvalidFixedValue(u8),
+    InvalidLogo,
 }
 
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            HeaderError::IncompleteHeader => f.write_str("incomplete ROM header"),
+            HeaderError::InvalidField { field } => {
+                write!(f, "invalid {}: wrong length or non-ASCII characters", field)
+            }
+            HeaderError::InvalidChecksum { expected, computed } => write!(
+                f,
+                "invalid header checksum: computed {:#04X}, expected {:#04X}",
+                computed, expected,
+            ),
+            HeaderError::InvalidFixedValue(value) => {
+                write!(f, "invalid fixed value: {:#04X}, expected 0x96", value)
+            }
+            HeaderError::InvalidLogo => f.write_str("invalid Nintendo logo"),
+        }
+    }
+}
+
+impl core::error::Error for HeaderError {}
+
 impl CartridgeHeader {
+    /// Creates a header with the given fields and a valid checksum.
+    ///
+    /// The game title can be up to 12 characters, the game code must be 4
+    /// characters and the maker code must be 2 characters. All fields must be
+    /// ASCII, without `NUL` characters.
+    pub fn new(
+        game_title: &str,
+        game_code: &str,
+        maker_code: &str,
+        software_version: u8,
+    ) -> Result<CartridgeHeader, HeaderError> {
+        let mut header = CartridgeHeader {
+            game_title: ascii_field("game title", game_title, false)?,
+            game_code: ascii_field("game code", game_code, true)?,
+            maker_code: ascii_field("maker code", maker_code, true)?,
+            software_version,
+            checksum: 0,
+        };
+        header.checksum = compute_checksum(&header.to_bytes()[CHECKSUM_RANGE]);
+        Ok(header)
+    }
+
     /// Parse header information from the first 192 bytes located at
     /// `0x8000000` in ROM.
+    ///
+    /// The checksum, fixed value and logo are required by the BIOS, but are
+    /// often wrong in homebrew ROMs, so an invalid checksum is only logged as a
+    /// warning. Use [`CartridgeHeader::parse_strict`] to reject them.
     pub fn parse(bytes: &[u8]) -> Result<CartridgeHeader, HeaderError> {
         if bytes.len() < HEADER_MIN_SIZE {
             return Err(HeaderError::IncompleteHeader);
         }
 
         let checksum = bytes[CHECKSUM_OFFSET];
-        let computed_checksum = compute_checksum(&bytes[CHECKSUM_RANGE]);
-        if checksum != computed_checksum {
+        if !CartridgeHeader::checksum_valid(bytes) {
+            let computed_checksum = compute_checksum(&bytes[CHECKSUM_RANGE]);
             log::warn!(
                 "invalid header checksum: computed {:#04X}, expected {:#04X}",
                 computed_checksum,
@@ -106,6 +192,141 @@ impl CartridgeHeader {
             checksum,
         })
     }
+
+    /// Parse header information from the first 192 bytes located at
+    /// `0x8000000` in ROM, rejecting headers with an invalid checksum, fixed
+    /// value or logo.
+    pub fn parse_strict(bytes: &[u8]) -> Result<CartridgeHeader, HeaderError> {
+        if bytes.len() < HEADER_MIN_SIZE {
+            return Err(HeaderError::IncompleteHeader);
+        }
+
+        if bytes[LOGO_RANGE] != NINTENDO_LOGO {
+            return Err(HeaderError::InvalidLogo);
+        }
+        if bytes[FIXED_VALUE_OFFSET] != FIXED_VALUE {
+            return Err(HeaderError::InvalidFixedValue(bytes[FIXED_VALUE_OFFSET]));
+        }
+        let computed = compute_checksum(&bytes[CHECKSUM_RANGE]);
+        if bytes[CHECKSUM_OFFSET] != computed {
+            return Err(HeaderError::InvalidChecksum {
+                expected: bytes[CHECKSUM_OFFSET],
+                computed,
+            });
+        }
+
+        CartridgeHeader::parse(bytes)
+    }
+}
+
+impl CartridgeHeader {
+    /// Returns the checksum computed from the header in the first 192 bytes
+    /// of a ROM, or `None` if there are too few bytes.
+    pub fn computed_checksum(bytes: &[u8]) -> Option<u8> {
+        bytes.get(CHECKSUM_RANGE).map(compute_checksum)
+    }
+
+    /// Checks if the checksum stored in the header in the first 192 bytes of a
+    /// ROM matches the computed checksum.
+    pub fn checksum_valid(bytes: &[u8]) -> bool {
+        match (
+            bytes.get(CHECKSUM_OFFSET),
+            CartridgeHeader::computed_checksum(bytes),
+        ) {
+            (Some(&checksum), Some(computed)) => checksum == computed,
+            _ => false,
+        }
+    }
+
+    /// Serializes the header into the first 192 bytes of a ROM.
+    ///
+    /// The entry point is a branch to `0xC0`, directly after the header, and
+    /// the checksum is recomputed from the other fields.
+    pub fn to_bytes(&self) -> [u8; HEADER_MIN_SIZE] {
+        let mut bytes = [0; HEADER_MIN_SIZE];
+
+        bytes[ENTRY_POINT_OFFSET..ENTRY_POINT_OFFSET + 4]
+            .copy_from_slice(&ENTRY_POINT.to_le_bytes());
+        bytes[LOGO_RANGE].copy_from_slice(&NINTENDO_LOGO);
+        bytes[GAME_TITLE_RANGE].copy_from_slice(&self.game_title.buf);
+        bytes[GAME_CODE_RANGE].copy_from_slice(&self.game_code.buf);
+        bytes[MAKER_CODE_RANGE].copy_from_slice(&self.maker_code.buf);
+        bytes[FIXED_VALUE_OFFSET] = FIXED_VALUE;
+        bytes[SOFTWARE_VERSION_OFFSET] = self.software_version;
+        bytes[CHECKSUM_OFFSET] = compute_checksum(&bytes[CHECKSUM_RANGE]);
+
+        bytes
+    }
+}
+
+/// A view of the header in the first 192 bytes of a ROM, reading fields
+/// directly from the borrowed bytes.
+///
+/// Unlike [`CartridgeHeader::parse`], the checksum is not verified.
+#[derive(Clone, Copy, Debug)]
+pub struct HeaderView<'a>(&'a [u8; HEADER_MIN_SIZE]);
+
+impl<'a> HeaderView<'a> {
+    /// Creates a view of the header in the first 192 bytes of `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<HeaderView<'a>, HeaderError> {
+        match bytes.get(..HEADER_MIN_SIZE) {
+            Some(header) => Ok(HeaderView(header.try_into().unwrap())),
+            None => Err(HeaderError::IncompleteHeader),
+        }
+    }
+
+    /// Returns the game title (uppercase ASCII, padded with `0x00`).
+    pub fn game_title(self) -> &'a Ascii<12> {
+        self.ascii(GAME_TITLE_RANGE)
+    }
+
+    /// Returns the game code (uppercase ASCII).
+    pub fn game_code(self) -> &'a Ascii<4> {
+        self.ascii(GAME_CODE_RANGE)
+    }
+
+    /// Returns the maker code (uppercase ASCII).
+    pub fn maker_code(self) -> &'a Ascii<2> {
+        self.ascii(MAKER_CODE_RANGE)
+    }
+
+    /// Returns the software version (usually `0x00`).
+    pub fn software_version(self) -> u8 {
+        self.0[SOFTWARE_VERSION_OFFSET]
+    }
+
+    /// Returns the header checksum stored in the ROM.
+    pub fn checksum(self) -> u8 {
+        self.0[CHECKSUM_OFFSET]
+    }
+
+    fn ascii<const N: usize>(self, range: Range<usize>) -> &'a Ascii<N> {
+        Ascii::from_array_ref(self.0[range].try_into().unwrap())
+    }
+}
+
+impl Cartridge {
+    /// Returns a view of the header in the ROM.
+    pub fn header_view(&self) -> HeaderView<'_> {
+        match HeaderView::new(&self.rom) {
+            Ok(view) => view,
+            Err(_) => unreachable!("loaded ROM contains a header"),
+        }
+    }
+}
+
+/// Converts a string into a header field of `N` bytes, padded with `0x00`.
+///
+/// If `exact` is set the string must fill the field.
+fn ascii_field<const N: usize>(
+    field: &'static str,
+    s: &str,
+    exact: bool,
+) -> Result<Ascii<N>, HeaderError> {
+    match Ascii::from_str_padded(s) {
+        Ok(ascii) if !exact || ascii.len() == N => Ok(ascii),
+        _ => Err(HeaderError::InvalidField { field }),
+    }
 }
 
 /// Compute header checksum over `0xA0..=0xBC`.
@@ -113,7 +334,7 @@ impl CartridgeHeader {
 /// # Sources
 ///
 /// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartridgeheader>
-fn compute_checksum(bytes: &[u8]) -> u8 {
+pub(crate) fn compute_checksum(bytes: &[u8]) -> u8 {
     debug_assert_eq!(bytes.len(), CHECKSUM_RANGE.len());
 
     let mut chk = 0u8;
@@ -125,7 +346,7 @@ fn compute_checksum(bytes: &[u8]) -> u8 {
 
 #[cfg(test)]
 mod tests {
-    use crate::util::test::ROM;
+    use crate::util::test::{capture_logs, ROM};
 
     use super::*;
 
@@ -146,4 +367,87 @@ mod tests {
 
         assert_eq!(header.checksum, compute_checksum(&ROM[CHECKSUM_RANGE]));
     }
+
+    #[test]
+    fn new_round_trip() {
+        let header = CartridgeHeader::new("HOMEBREW", "BHBE", "01", 0x01).unwrap();
+        let bytes = header.to_bytes();
+        let parsed = CartridgeHeader::parse(&bytes).unwrap();
+
+        assert_eq!(parsed.game_title, "HOMEBREW");
+        assert_eq!(parsed.game_code, "BHBE");
+        assert_eq!(parsed.maker_code, "01");
+        assert_eq!(parsed.software_version, 0x01);
+        assert_eq!(parsed.checksum, header.checksum);
+        assert_eq!(parsed.checksum, compute_checksum(&bytes[CHECKSUM_RANGE]));
+        assert_eq!(bytes[LOGO_RANGE], ROM[LOGO_RANGE]);
+        assert_eq!(bytes[FIXED_VALUE_OFFSET], FIXED_VALUE);
+    }
+
+    #[test]
+    fn parse_strict() {
+        assert!(CartridgeHeader::parse_strict(ROM).is_ok());
+
+        let mut rom = ROM[..HEADER_MIN_SIZE].to_vec();
+        rom[CHECKSUM_OFFSET] ^= 0xFF;
+        let (header, logs) = capture_logs(|| CartridgeHeader::parse(&rom));
+        assert!(header.is_ok());
+        assert_eq!(
+            logs,
+            [(
+                log::Level::Warn,
+                "invalid header checksum: computed 0x69, expected 0x96".to_string(),
+            )]
+        );
+        let (_, logs) = capture_logs(|| CartridgeHeader::parse(ROM));
+        assert!(logs.is_empty(), "{:?}", logs);
+        assert!(matches!(
+            CartridgeHeader::parse_strict(&rom),
+            Err(HeaderError::InvalidChecksum {
+                expected: 0x96,
+                computed: 0x69,
+            })
+        ));
+
+        let mut rom = ROM[..HEADER_MIN_SIZE].to_vec();
+        rom[FIXED_VALUE_OFFSET] = 0;
+        assert!(matches!(
+            CartridgeHeader::parse_strict(&rom),
+            Err(HeaderError::InvalidFixedValue(0))
+        ));
+
+        let mut rom = ROM[..HEADER_MIN_SIZE].to_vec();
+        rom[LOGO_RANGE.start] ^= 0xFF;
+        assert!(matches!(
+            CartridgeHeader::parse_strict(&rom),
+            Err(HeaderError::InvalidLogo)
+        ));
+    }
+
+    #[test]
+    fn header_view() {
+        let header = CartridgeHeader::parse(ROM).unwrap();
+        let view = HeaderView::new(ROM).unwrap();
+
+        assert_eq!(*view.game_title(), header.game_title);
+        assert_eq!(*view.game_code(), header.game_code);
+        assert_eq!(*view.maker_code(), header.maker_code);
+        assert_eq!(view.software_version(), header.software_version);
+        assert_eq!(view.checksum(), header.checksum);
+
+        let cart = Cartridge::load_from_bytes(ROM).unwrap();
+        assert_eq!(cart.header_view().game_title(), "GBA Tests");
+
+        assert!(matches!(
+            HeaderView::new(&ROM[..HEADER_MIN_SIZE - 1]),
+            Err(HeaderError::IncompleteHeader)
+        ));
+    }
+
+    #[test]
+    fn new_invalid_field() {
+        assert!(CartridgeHeader::new("A VERY LONG TITLE", "BHBE", "01", 0).is_err());
+        assert!(CartridgeHeader::new("HOMEBREW", "BHB", "01", 0).is_err());
+        assert!(CartridgeHeader::new("HOMEBREW", "BHBE", "\u{e9}", 0).is_err());
+    }
 }
@@ -0,0 +1,30 @@
+/// Multiboot images are loaded into the 256 KiB of on-board work RAM.
+pub(crate) const MULTIBOOT_MAX_SIZE: usize = 0x40000;
+
+/// Address cartridge ROM is mapped at.
+pub(crate) const CARTRIDGE_BASE_ADDRESS: u32 = 0x0800_0000;
+
+/// Address multiboot images are loaded at, the start of on-board work RAM.
+pub(crate) const MULTIBOOT_BASE_ADDRESS: u32 = 0x0200_0000;
+
+/// Offset of the multiboot RAM entry point.
+pub(crate) const RAM_ENTRY_POINT_OFFSET: usize = 0xC0;
+
+/// The kind of image loaded into a cartridge.
+///
+/// The kind is set by how the image is loaded, see
+/// [`Cartridge::load_from_bytes`] and [`Cartridge::load_multiboot_from_bytes`].
+///
+/// [`Cartridge::load_from_bytes`]: crate::Cartridge::load_from_bytes
+/// [`Cartridge::load_multiboot_from_bytes`]: crate::Cartridge::load_multiboot_from_bytes
+///
+/// # Sources
+///
+/// \[1\]: <https://problemkaputt.de/gbatek.htm#gbacartridgeheader>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageKind {
+    /// A cartridge ROM, mapped at `0x8000000`.
+    Cartridge,
+    /// A multiboot image, transferred into work RAM at `0x2000000`.
+    Multiboot,
+}
@@ -1,12 +1,38 @@
+//! GBA cartridge loading.
+//!
+//! The `std` feature, enabled by default, adds loading ROMs from files. Without
+//! it the crate only depends on `core` and `alloc`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod backup;
+mod compat;
 mod header;
+mod image;
 mod load;
+mod patch;
+mod rom;
+mod strings;
+mod title;
 mod util;
 
-pub use crate::header::{CartridgeHeader, HeaderError};
+pub use crate::backup::{Eeprom, Flash, SaveError, SaveType};
+pub use crate::compat::{CompatReport, Region};
+pub use crate::header::{CartridgeHeader, HeaderError, HeaderView};
+pub use crate::image::ImageKind;
+pub use crate::load::LoadError;
+pub use crate::patch::PatchError;
+
+use alloc::boxed::Box;
 
 /// GBA cartridge.
 #[derive(Clone, Debug)]
 pub struct Cartridge {
     pub header: CartridgeHeader,
+    kind: ImageKind,
+    save_type: SaveType,
+    save: Box<[u8]>,
     rom: Box<[u8]>,
 }
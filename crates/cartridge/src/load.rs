@@ -1,19 +1,177 @@
+use alloc::boxed::Box;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, io, io::Read, path::Path};
+
+use crate::backup::detect_save_type;
 use crate::header::{CartridgeHeader, HeaderError};
+use crate::image::{ImageKind, MULTIBOOT_MAX_SIZE};
 use crate::Cartridge;
 
 /// An error loading a cartridge.
-#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[derive(Debug)]
 pub enum LoadError {
     /// An error parsing the cartridge header.
-    #[error(transparent)]
-    HeaderError(#[from] HeaderError),
+    HeaderError(HeaderError),
+    /// A multiboot image larger than the 256 KiB of work RAM it is loaded into.
+    MultibootTooLarge(usize),
+    /// An error reading the ROM.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl From<HeaderError> for LoadError {
+    fn from(err: HeaderError) -> LoadError {
+        LoadError::HeaderError(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        LoadError::Io(err)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::HeaderError(err) => err.fmt(f),
+            LoadError::MultibootTooLarge(size) => write!(
+                f,
+                "multiboot image too large, {} bytes exceeds {} bytes",
+                size, MULTIBOOT_MAX_SIZE
+            ),
+            #[cfg(feature = "std")]
+            LoadError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            LoadError::HeaderError(_) | LoadError::MultibootTooLarge(_) => None,
+            #[cfg(feature = "std")]
+            LoadError::Io(err) => err.source(),
+        }
+    }
 }
 
 impl Cartridge {
+    /// Loads a cartridge ROM, mapped at `0x8000000`.
     pub fn load_from_bytes<B: Into<Box<[u8]>>>(rom: B) -> Result<Cartridge, LoadError> {
+        Cartridge::load(rom.into(), ImageKind::Cartridge, CartridgeHeader::parse)
+    }
+
+    /// Loads a multiboot image, transferred into work RAM at `0x2000000`.
+    ///
+    /// Fails if the image does not fit in the 256 KiB of work RAM.
+    pub fn load_multiboot_from_bytes<B: Into<Box<[u8]>>>(rom: B) -> Result<Cartridge, LoadError> {
         let rom = rom.into();
-        let header = CartridgeHeader::parse(&rom)?;
+        if rom.len() > MULTIBOOT_MAX_SIZE {
+            return Err(LoadError::MultibootTooLarge(rom.len()));
+        }
+        Cartridge::load(rom, ImageKind::Multiboot, CartridgeHeader::parse)
+    }
+
+    /// Loads a cartridge, rejecting ROMs with an invalid header checksum,
+    /// fixed value or logo.
+    ///
+    /// See [`CartridgeHeader::parse_strict`].
+    pub fn load_from_bytes_strict<B: Into<Box<[u8]>>>(rom: B) -> Result<Cartridge, LoadError> {
+        Cartridge::load(
+            rom.into(),
+            ImageKind::Cartridge,
+            CartridgeHeader::parse_strict,
+        )
+    }
+
+    /// Loads a cartridge from a ROM file.
+    #[cfg(feature = "std")]
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Cartridge, LoadError> {
+        Cartridge::load_from_bytes(fs::read(path)?)
+    }
+
+    /// Loads a cartridge from a ROM read to the end of `reader`.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Cartridge, LoadError> {
+        let mut rom = Vec::new();
+        reader.read_to_end(&mut rom)?;
+        Cartridge::load_from_bytes(rom)
+    }
+
+    fn load(
+        rom: Box<[u8]>,
+        kind: ImageKind,
+        parse: fn(&[u8]) -> Result<CartridgeHeader, HeaderError>,
+    ) -> Result<Cartridge, LoadError> {
+        let header = parse(&rom)?;
+        let save_type = detect_save_type(&rom);
+
+        Ok(Cartridge {
+            header,
+            kind,
+            save_type,
+            save: save_type.blank(),
+            rom,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::header::HEADER_MIN_SIZE;
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    #[test]
+    fn header_error() {
+        let err = Cartridge::load_from_bytes(&ROM[..0x40]).unwrap_err();
+        assert!(matches!(
+            err,
+            LoadError::HeaderError(HeaderError::IncompleteHeader)
+        ));
+        assert_eq!(err.to_string(), "incomplete ROM header");
+    }
+
+    #[test]
+    fn multiboot_too_large() {
+        let mut rom = ROM[..HEADER_MIN_SIZE].to_vec();
+        rom.resize(0x40001, 0);
+        let err = Cartridge::load_multiboot_from_bytes(rom).unwrap_err();
+        assert!(matches!(err, LoadError::MultibootTooLarge(0x40001)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn load_from_path() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../external/gba-tests/ppu/stripes.gba"
+        );
+        let cart = Cartridge::load_from_path(path).unwrap();
+        assert_eq!(cart.header.game_title, "GBA Tests");
+
+        let err = Cartridge::load_from_path("missing.gba").unwrap_err();
+        assert!(matches!(err, LoadError::Io(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn from_reader() {
+        let cart = Cartridge::from_reader(io::Cursor::new(ROM)).unwrap();
+        assert_eq!(cart.header.game_title, "GBA Tests");
+        assert_eq!(cart.read8(0x0800_0000), ROM[0]);
 
-        Ok(Cartridge { header, rom })
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::UnexpectedEof.into())
+            }
+        }
+        let err = Cartridge::from_reader(Failing).unwrap_err();
+        assert!(matches!(err, LoadError::Io(_)));
     }
 }
@@ -0,0 +1,203 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::backup::detect_save_type;
+use crate::header::{CartridgeHeader, HeaderError};
+use crate::image::{ImageKind, MULTIBOOT_MAX_SIZE};
+use crate::Cartridge;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+
+/// An error applying a patch.
+#[derive(Clone, Copy, Debug)]
+pub enum PatchError {
+    /// The patch does not start with the `PATCH` magic.
+    InvalidHeader,
+    /// The patch ends in the middle of a record, or without the `EOF` marker.
+    UnexpectedEof,
+    /// An error parsing the patched cartridge header.
+    HeaderError(HeaderError),
+    /// A patched multiboot image larger than the 256 KiB of work RAM it is
+    /// loaded into.
+    MultibootTooLarge(usize),
+}
+
+impl From<HeaderError> for PatchError {
+    fn from(err: HeaderError) -> PatchError {
+        PatchError::HeaderError(err)
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidHeader => f.write_str("missing IPS patch header"),
+            PatchError::UnexpectedEof => f.write_str("unexpected end of IPS patch"),
+            PatchError::HeaderError(err) => err.fmt(f),
+            PatchError::MultibootTooLarge(size) => write!(
+                f,
+                "patched multiboot image too large, {} bytes exceeds {} bytes",
+                size, MULTIBOOT_MAX_SIZE
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PatchError {}
+
+impl Cartridge {
+    /// Applies an IPS patch to the ROM.
+    ///
+    /// Records past the end of the ROM extend it. The header and save type are
+    /// detected again from the patched ROM, erasing the backup memory if the
+    /// save type changes. If the patch is invalid, or extends a multiboot image
+    /// past the 256 KiB of work RAM, the ROM is left unchanged.
+    ///
+    /// # IPS Format
+    ///
+    /// ```text
+    /// Bytes  Expl.
+    /// 5      "PATCH"
+    /// ...    Records
+    /// 3      "EOF"
+    ///
+    /// Record:
+    /// 3      Offset (big-endian)
+    /// 2      Size (big-endian)
+    /// Size   Data
+    ///
+    /// RLE record (size of 0):
+    /// 3      Offset (big-endian)
+    /// 2      0x0000
+    /// 2      Run length (big-endian)
+    /// 1      Value
+    /// ```
+    pub fn apply_ips(&mut self, patch: &[u8]) -> Result<(), PatchError> {
+        let mut patch = patch
+            .strip_prefix(IPS_MAGIC)
+            .ok_or(PatchError::InvalidHeader)?;
+        let mut rom = self.rom.to_vec();
+
+        loop {
+            if patch.starts_with(IPS_EOF) {
+                break;
+            }
+
+            let offset = take(&mut patch, 3)?;
+            let offset = u32::from_be_bytes([0, offset[0], offset[1], offset[2]]) as usize;
+            let size = take(&mut patch, 2)?;
+            let size = u16::from_be_bytes([size[0], size[1]]) as usize;
+
+            if size == 0 {
+                let len = take(&mut patch, 2)?;
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                let value = take(&mut patch, 1)?[0];
+
+                resize_to(&mut rom, offset + len);
+                rom[offset..offset + len].fill(value);
+            } else {
+                let data = take(&mut patch, size)?;
+
+                resize_to(&mut rom, offset + size);
+                rom[offset..offset + size].copy_from_slice(data);
+            }
+        }
+
+        if self.kind == ImageKind::Multiboot && rom.len() > MULTIBOOT_MAX_SIZE {
+            return Err(PatchError::MultibootTooLarge(rom.len()));
+        }
+
+        self.header = CartridgeHeader::parse(&rom)?;
+        let save_type = detect_save_type(&rom);
+        if save_type != self.save_type {
+            self.save_type = save_type;
+            self.save = save_type.blank();
+        }
+        self.rom = rom.into_boxed_slice();
+
+        Ok(())
+    }
+}
+
+/// Takes `n` bytes from the front of `patch`.
+fn take<'a>(patch: &mut &'a [u8], n: usize) -> Result<&'a [u8], PatchError> {
+    if patch.len() < n {
+        return Err(PatchError::UnexpectedEof);
+    }
+    let (bytes, rest) = patch.split_at(n);
+    *patch = rest;
+    Ok(bytes)
+}
+
+/// Extends `rom` with zeros to at least `len` bytes.
+fn resize_to(rom: &mut Vec<u8>, len: usize) {
+    if rom.len() < len {
+        rom.resize(len, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    #[test]
+    fn apply() {
+        let mut cart = Cartridge::load_from_bytes(ROM).unwrap();
+        let len = ROM.len();
+
+        let mut patch = b"PATCH".to_vec();
+        // Replace the game code.
+        patch.extend_from_slice(&[0x00, 0x00, 0xAC, 0x00, 0x04]);
+        patch.extend_from_slice(b"ABCE");
+        // Fill 4 bytes at 0x100 with 0xFF.
+        patch.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0xFF]);
+        // Extend the ROM by 2 bytes.
+        let end = (len as u32).to_be_bytes();
+        patch.extend_from_slice(&[end[1], end[2], end[3], 0x00, 0x02, 0x12, 0x34]);
+        patch.extend_from_slice(b"EOF");
+
+        cart.apply_ips(&patch).unwrap();
+
+        assert_eq!(cart.header.game_code, "ABCE");
+        assert_eq!(cart.header.game_title, "GBA Tests");
+        assert_eq!(cart.rom[0x100..0x104], [0xFF; 4]);
+        assert_eq!(cart.rom.len(), len + 2);
+        assert_eq!(cart.rom[len..], [0x12, 0x34]);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut cart = Cartridge::load_from_bytes(ROM).unwrap();
+
+        assert!(matches!(
+            cart.apply_ips(b"PTCH"),
+            Err(PatchError::InvalidHeader)
+        ));
+        assert!(matches!(
+            cart.apply_ips(b"PATCH\x00\x00\xAC\x00\x04AB"),
+            Err(PatchError::UnexpectedEof)
+        ));
+        assert_eq!(cart.header.game_code, "1337");
+        assert_eq!(&cart.rom[..], ROM);
+    }
+
+    #[test]
+    fn multiboot_too_large() {
+        let mut cart = Cartridge::load_multiboot_from_bytes(ROM).unwrap();
+
+        // Write a byte just past the end of work RAM.
+        let patch = b"PATCH\x04\x00\x00\x00\x01\xFFEOF";
+        assert!(matches!(
+            cart.apply_ips(patch),
+            Err(PatchError::MultibootTooLarge(0x40001))
+        ));
+        assert_eq!(&cart.rom[..], ROM);
+
+        let mut cart = Cartridge::load_from_bytes(ROM).unwrap();
+        cart.apply_ips(patch).unwrap();
+        assert_eq!(cart.rom.len(), 0x40001);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::header::HEADER_MIN_SIZE;
+use crate::image::{
+    ImageKind, CARTRIDGE_BASE_ADDRESS, MULTIBOOT_BASE_ADDRESS, MULTIBOOT_MAX_SIZE,
+    RAM_ENTRY_POINT_OFFSET,
+};
+use crate::Cartridge;
+
+/// Cartridge ROM is mirrored every 32 MiB.
+const CARTRIDGE_MIRROR_SIZE: usize = 0x2000000;
+
+impl Cartridge {
+    /// Returns the kind of the loaded image.
+    pub fn kind(&self) -> ImageKind {
+        self.kind
+    }
+
+    /// Returns the address the image is mapped at.
+    ///
+    /// Cartridge ROM is mapped at `0x8000000`, whereas a multiboot image is
+    /// loaded into work RAM at `0x2000000`.
+    pub fn base_address(&self) -> u32 {
+        match self.kind {
+            ImageKind::Cartridge => CARTRIDGE_BASE_ADDRESS,
+            ImageKind::Multiboot => MULTIBOOT_BASE_ADDRESS,
+        }
+    }
+
+    /// Returns the address execution starts at once the image is loaded.
+    ///
+    /// Cartridges start at the ROM entry point at `0x8000000`, whereas
+    /// multiboot images start at the RAM entry point at `0x20000C0`.
+    pub fn entry_point(&self) -> u32 {
+        match self.kind {
+            ImageKind::Cartridge => CARTRIDGE_BASE_ADDRESS,
+            ImageKind::Multiboot => MULTIBOOT_BASE_ADDRESS + RAM_ENTRY_POINT_OFFSET as u32,
+        }
+    }
+
+    /// Returns the mask used to wrap addresses into the loaded image.
+    ///
+    /// Cartridge ROM is mirrored every 32 MiB, whereas a multiboot image is
+    /// mirrored with the 256 KiB of work RAM it is loaded into.
+    pub fn address_mask(&self) -> u32 {
+        let size = match self.kind {
+            ImageKind::Cartridge => CARTRIDGE_MIRROR_SIZE,
+            ImageKind::Multiboot => MULTIBOOT_MAX_SIZE,
+        };
+        (size - 1) as u32
+    }
+
+    /// Returns the ROM with any trailing padding removed.
+    ///
+    /// ROM dumps are usually padded up to a power of two with `0x00` or `0xFF`
+    /// bytes. The padding is only trimmed when the ROM is a power of two in
+    /// size and the data before it would round up to that same size. Data
+    /// is kept to a word boundary, and the header is always kept, so that
+    /// trailing bytes of the last word that match the padding are not lost.
+    pub fn trimmed_rom(&self) -> &[u8] {
+        let rom = &self.rom[..];
+        let (&pad, _) = match rom.split_last() {
+            Some(last) if rom.len().is_power_of_two() => last,
+            _ => return rom,
+        };
+        if pad != 0x00 && pad != 0xFF {
+            return rom;
+        }
+
+        let data_len = rom.iter().rposition(|&b| b != pad).map_or(0, |i| i + 1);
+        let len = (data_len.next_multiple_of(4)).max(HEADER_MIN_SIZE);
+        if len.next_power_of_two() == rom.len() {
+            &rom[..len]
+        } else {
+            rom
+        }
+    }
+
+    /// Reads a byte from the image at `addr`.
+    pub fn read8(&self, addr: u32) -> u8 {
+        let offset = (addr.wrapping_sub(self.base_address()) & self.address_mask()) as usize;
+
+        match self.rom.get(offset) {
+            Some(&b) => b,
+            // Reading past the end of cartridge ROM returns the lower 16 bits
+            // of the halfword address on the bus.
+            None if self.kind == ImageKind::Cartridge => (offset >> 1 >> ((offset & 1) * 8)) as u8,
+            None => 0,
+        }
+    }
+
+    /// Reads a little-endian halfword from the image at `addr`.
+    pub fn read16(&self, addr: u32) -> u16 {
+        u16::from_le_bytes([self.read8(addr), self.read8(addr.wrapping_add(1))])
+    }
+
+    /// Reads a little-endian word from the image at `addr`.
+    pub fn read32(&self, addr: u32) -> u32 {
+        u32::from_le_bytes([
+            self.read8(addr),
+            self.read8(addr.wrapping_add(1)),
+            self.read8(addr.wrapping_add(2)),
+            self.read8(addr.wrapping_add(3)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    fn image(size: usize, ram_entry: u32) -> Vec<u8> {
+        let mut rom = vec![0; size];
+        rom[..0xC0].copy_from_slice(&ROM[..0xC0]);
+        rom[0xC0..0xC4].copy_from_slice(&ram_entry.to_le_bytes());
+        rom
+    }
+
+    #[test]
+    fn cartridge_mask() {
+        let cart = Cartridge::load_from_bytes(image(0x80000, 0xEA00_0000)).unwrap();
+
+        assert_eq!(cart.kind(), ImageKind::Cartridge);
+        assert_eq!(cart.address_mask(), 0x01FF_FFFF);
+        assert_eq!(cart.read32(0x0800_00C0), 0xEA00_0000);
+        assert_eq!(cart.read32(0x0A00_00C0), 0xEA00_0000);
+    }
+
+    #[test]
+    fn base_address() {
+        let mut rom = image(0x8000, 0xEA00_0000);
+        rom[0x1234] = 0xAB;
+        let multiboot = Cartridge::load_multiboot_from_bytes(rom.clone()).unwrap();
+        let cartridge = Cartridge::load_from_bytes(rom).unwrap();
+
+        assert_eq!(multiboot.base_address(), 0x0200_0000);
+        assert_eq!(cartridge.base_address(), 0x0800_0000);
+        assert_eq!(multiboot.read8(0x0200_1234), 0xAB);
+        assert_eq!(cartridge.read8(0x0800_1234), 0xAB);
+    }
+
+    #[test]
+    fn entry_point() {
+        let cartridge = Cartridge::load_from_bytes(image(0x80000, 0xEA00_0000)).unwrap();
+        let multiboot = Cartridge::load_multiboot_from_bytes(image(0x8000, 0xEA00_0000)).unwrap();
+
+        assert_eq!(cartridge.entry_point(), 0x0800_0000);
+        assert_eq!(multiboot.entry_point(), 0x0200_00C0);
+    }
+
+    #[test]
+    fn small_cartridge() {
+        // A small ROM branching over the header from 0xC0, as with devkitARM's
+        // crt0, is still a cartridge.
+        let cart = Cartridge::load_from_bytes(image(0x8000, 0xEA00_0000)).unwrap();
+
+        assert_eq!(cart.kind(), ImageKind::Cartridge);
+        assert_eq!(cart.base_address(), 0x0800_0000);
+        assert_eq!(cart.entry_point(), 0x0800_0000);
+    }
+
+    #[test]
+    fn trimmed_rom() {
+        let mut rom = image(0x80000, 0xEA00_0000);
+        rom[0x4_1232] = 0xFF;
+        rom[0x4_1234..].fill(0xFF);
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), 0x4_1234);
+
+        // Data ending in the padding byte is kept up to a word boundary.
+        let mut rom = image(0x80000, 0xEA00_0000);
+        rom[0x4_1231] = 0x12;
+        rom[0x4_1232..].fill(0xFF);
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), 0x4_1234);
+        assert_eq!(cart.trimmed_rom()[0x4_1230..], [0x00, 0x12, 0xFF, 0xFF]);
+
+        // Zero padding is trimmed, but never into the header.
+        let mut rom = image(0x80000, 0xEA00_0000);
+        rom[0x4_0100] = 0x12;
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), 0x4_0104);
+
+        let cart = Cartridge::load_from_bytes(image(0x100, 0)).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), HEADER_MIN_SIZE);
+    }
+
+    #[test]
+    fn trimmed_rom_unpadded() {
+        // Not a power of two, so not padded.
+        let mut rom = image(0x6_0000, 0xEA00_0000);
+        rom[0x5_0000..].fill(0xFF);
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), 0x6_0000);
+
+        // Trimming would shrink the ROM below the next smaller power of two,
+        // so the trailing bytes are data rather than padding.
+        let mut rom = image(0x80000, 0xEA00_0000);
+        rom[0x1_0000..].fill(0xFF);
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+        assert_eq!(cart.trimmed_rom().len(), 0x8_0000);
+    }
+
+    #[test]
+    fn multiboot_mask() {
+        let cart = Cartridge::load_multiboot_from_bytes(image(0x8000, 0xEA00_0000)).unwrap();
+
+        assert_eq!(cart.kind(), ImageKind::Multiboot);
+        assert_eq!(cart.address_mask(), 0x0003_FFFF);
+        assert_eq!(cart.read32(0x0200_00C0), 0xEA00_0000);
+        assert_eq!(cart.read32(0x0204_00C0), 0xEA00_0000);
+    }
+}
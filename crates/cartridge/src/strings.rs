@@ -0,0 +1,63 @@
+use core::{iter, str};
+
+use crate::Cartridge;
+
+impl Cartridge {
+    /// Returns an iterator over runs of printable ASCII in the ROM at least
+    /// `min_len` bytes long, yielding the offset and content of each run.
+    pub fn strings(&self, min_len: usize) -> impl Iterator<Item = (usize, &str)> {
+        strings(&self.rom, min_len)
+    }
+}
+
+/// Returns an iterator over runs of printable ASCII in `rom` at least
+/// `min_len` bytes long, yielding the offset and content of each run.
+pub(crate) fn strings(rom: &[u8], min_len: usize) -> impl Iterator<Item = (usize, &str)> {
+    let is_printable = |c: &u8| (0x20..=0x7E).contains(c);
+
+    let mut offset = 0;
+    iter::from_fn(move || {
+        while offset < rom.len() {
+            let start = match rom[offset..].iter().position(is_printable) {
+                Some(i) => offset + i,
+                None => break,
+            };
+            let len = rom[start..]
+                .iter()
+                .position(|c| !is_printable(c))
+                .unwrap_or(rom.len() - start);
+            offset = start + len;
+
+            if len >= min_len {
+                // SAFETY: The run contains only printable ASCII.
+                let s = unsafe { str::from_utf8_unchecked(&rom[start..offset]) };
+                return Some((start, s));
+            }
+        }
+
+        offset = rom.len();
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    #[test]
+    fn strings() {
+        let mut rom = ROM.to_vec();
+        rom.resize(0x400, 0);
+        rom[0x200..0x20B].copy_from_slice(b"FLASH_V121\xFF");
+        rom[0x300..0x30A].copy_from_slice(b"\x01ab\x02SIIRTC");
+        let cart = Cartridge::load_from_bytes(rom).unwrap();
+
+        let found: Vec<_> = cart
+            .strings(6)
+            .filter(|&(offset, _)| offset >= 0x200)
+            .collect();
+        assert_eq!(found, [(0x200, "FLASH_V121"), (0x304, "SIIRTC")]);
+    }
+}
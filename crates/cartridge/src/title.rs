@@ -0,0 +1,82 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+
+use crate::Cartridge;
+
+impl Cartridge {
+    /// Returns the game title, sanitized for use in a filename.
+    ///
+    /// Padding and surrounding whitespace are trimmed, and characters that are
+    /// not allowed in filenames (path separators, reserved punctuation, control
+    /// characters and non-ASCII bytes) are replaced by `_`. A title that is
+    /// empty or a relative path component (`.` or `..`) is replaced by `_`.
+    pub fn sanitized_title(&self) -> String {
+        let title: String = self
+            .header
+            .game_title
+            .as_bytes()
+            .iter()
+            .map(|&c| match c {
+                b'/' | b'\\' | b':' | b'*' | b'?' | b'"' | b'<' | b'>' | b'|' => '_',
+                c if c.is_ascii_control() || !c.is_ascii() => '_',
+                c => c as char,
+            })
+            .collect();
+
+        match title.trim() {
+            "" | "." | ".." => "_".to_owned(),
+            title => title.to_owned(),
+        }
+    }
+
+    /// Checks if the game title matches `needle`, ignoring ASCII case, padding
+    /// and surrounding whitespace.
+    pub fn title_matches(&self, needle: &str) -> bool {
+        let title: String = self.header.game_title.chars().collect();
+
+        title.trim().eq_ignore_ascii_case(needle.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::test::ROM;
+
+    use super::*;
+
+    fn with_title(title: &[u8]) -> Cartridge {
+        let mut rom = ROM.to_vec();
+        rom[0xA0..0xAC].fill(0);
+        rom[0xA0..0xA0 + title.len()].copy_from_slice(title);
+        Cartridge::load_from_bytes(rom).unwrap()
+    }
+
+    #[test]
+    fn sanitized_title() {
+        let cart = Cartridge::load_from_bytes(ROM).unwrap();
+
+        assert_eq!(cart.sanitized_title(), "GBA Tests");
+    }
+
+    #[test]
+    fn sanitized_title_illegal_characters() {
+        let cart = with_title(b"A/B\\C:D\x07E\xFF ");
+
+        assert_eq!(cart.sanitized_title(), "A_B_C_D_E_");
+
+        for title in [&b""[..], b"   ", b".", b"..", b" .. "] {
+            let cart = with_title(title);
+            assert_eq!(cart.sanitized_title(), "_", "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn title_matches() {
+        let cart = Cartridge::load_from_bytes(ROM).unwrap();
+
+        assert!(cart.title_matches("gba tests"));
+        assert!(cart.title_matches(" GBA TESTS "));
+        assert!(!cart.title_matches("gba test"));
+        assert!(!cart.title_matches("other game"));
+    }
+}
@@ -1,30 +1,57 @@
-use std::borrow::Cow;
-use std::fmt;
-use std::ops::Deref;
-use std::str;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::ops::Deref;
+use core::{slice, str};
 
 /// An error in an ASCII string.
 #[derive(Clone, Copy, Debug)]
 pub struct AsciiError {
     valid_up_to: usize,
+    kind: AsciiErrorKind,
+}
+
+/// The kind of an [`AsciiError`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AsciiErrorKind {
+    /// A non-ASCII character, or `NUL` when converting from a `str`.
+    Invalid,
+    /// A string longer than the capacity.
+    TooLong { capacity: usize },
 }
 
 impl AsciiError {
     /// Returns the index in the string up to which valid ASCII was verified.
+    ///
+    /// For a string that is too long, this is the length of the string.
     pub fn valid_up_to(&self) -> usize {
         self.valid_up_to
     }
+
+    /// Returns the kind of error.
+    pub fn kind(&self) -> AsciiErrorKind {
+        self.kind
+    }
 }
 
 impl fmt::Display for AsciiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "invalid ascii at index {}", self.valid_up_to)
+        match self.kind {
+            AsciiErrorKind::Invalid => write!(f, "invalid ascii at index {}", self.valid_up_to),
+            AsciiErrorKind::TooLong { capacity } => {
+                write!(f, "string too long, capacity is {}", capacity)
+            }
+        }
     }
 }
 
 /// An ASCII string with a fixed capacity, `N`.
 ///
-/// Terminated by `0x00` bytes if shorter than `N`.
+/// Terminated by `0x00` bytes if shorter than `N`. Bytes after the terminator
+/// are ignored when comparing, ordering and hashing strings.
 #[derive(Clone, Copy, Eq)]
 #[repr(transparent)]
 pub struct Ascii<const N: usize> {
@@ -38,6 +65,37 @@ impl<const N: usize> Ascii<N> {
         *(bytes.as_ptr() as *const Ascii<N>)
     }
 
+    /// Reinterprets a byte array as an ASCII string.
+    #[inline]
+    pub(crate) fn from_array_ref(bytes: &[u8; N]) -> &Ascii<N> {
+        // SAFETY: `Ascii<N>` is a transparent wrapper around `[u8; N]`.
+        unsafe { &*(bytes as *const [u8; N] as *const Ascii<N>) }
+    }
+
+    /// Converts a string into an ASCII string, padded with `0x00`.
+    ///
+    /// Fails if the string contains non-ASCII or `NUL` characters, or is longer
+    /// than `N`.
+    pub fn from_str_padded(s: &str) -> Result<Ascii<N>, AsciiError> {
+        let bytes = s.as_bytes();
+        if let Some(valid_up_to) = bytes.iter().position(|&c| c == 0 || c > 0x7F) {
+            return Err(AsciiError {
+                valid_up_to,
+                kind: AsciiErrorKind::Invalid,
+            });
+        }
+        if bytes.len() > N {
+            return Err(AsciiError {
+                valid_up_to: bytes.len(),
+                kind: AsciiErrorKind::TooLong { capacity: N },
+            });
+        }
+
+        let mut buf = [0; N];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(Ascii { buf })
+    }
+
     /// Returns the length of the string.
     pub fn len(&self) -> usize {
         // TODO: Custom optimised implementation.
@@ -49,6 +107,18 @@ impl<const N: usize> Ascii<N> {
         &self.buf[..self.len()]
     }
 
+    /// Returns an iterator over the characters of the string.
+    ///
+    /// Invalid characters are yielded as
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: char::REPLACEMENT_CHARACTER
+    pub fn chars(&self) -> Chars<'_> {
+        Chars {
+            iter: self.as_bytes().iter(),
+        }
+    }
+
     /// Returns the string content.
     pub fn to_str(&self) -> Result<&str, AsciiError> {
         validate_ascii(self.as_bytes())
@@ -63,7 +133,9 @@ impl<const N: usize> Ascii<N> {
 
         match validate_ascii(bytes) {
             Ok(s) => Cow::Borrowed(s),
-            Err(AsciiError { mut valid_up_to }) => {
+            Err(AsciiError {
+                mut valid_up_to, ..
+            }) => {
                 const REPLACEMENT: &str = "\u{FFFD}";
 
                 let mut res = String::with_capacity(bytes.len());
@@ -94,10 +166,55 @@ impl<const N: usize> Ascii<N> {
     }
 }
 
+/// An iterator over the characters of an [`Ascii`] string.
+///
+/// Created by [`Ascii::chars`].
+#[derive(Clone, Debug)]
+pub struct Chars<'a> {
+    iter: slice::Iter<'a, u8>,
+}
+
+#[inline]
+fn byte_to_char(c: u8) -> char {
+    if c.is_ascii() {
+        c as char
+    } else {
+        char::REPLACEMENT_CHARACTER
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.iter.next().copied().map(byte_to_char)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.iter.next_back().copied().map(byte_to_char)
+    }
+}
+
+impl ExactSizeIterator for Chars<'_> {}
+
+impl FusedIterator for Chars<'_> {}
+
 #[inline]
 fn validate_ascii(bytes: &[u8]) -> Result<&str, AsciiError> {
     match bytes.iter().position(|&c| c > 0x7F) {
-        Some(valid_up_to) => Err(AsciiError { valid_up_to }),
+        Some(valid_up_to) => Err(AsciiError {
+            valid_up_to,
+            kind: AsciiErrorKind::Invalid,
+        }),
         // SAFETY: We just validated the bytes.
         None => Ok(unsafe { str::from_utf8_unchecked(bytes) }),
     }
@@ -150,6 +267,25 @@ impl<const N: usize> PartialEq<Ascii<N>> for &str {
     }
 }
 
+impl<const N: usize> Hash for Ascii<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl<const N: usize> PartialOrd for Ascii<N> {
+    #[inline]
+    fn partial_cmp(&self, other: &Ascii<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for Ascii<N> {
+    fn cmp(&self, other: &Ascii<N>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
 impl<const N: usize> Deref for Ascii<N> {
     type Target = [u8; N];
 
@@ -158,3 +294,56 @@ impl<const N: usize> Deref for Ascii<N> {
         &self.buf
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_padded() {
+        let short = Ascii::<4>::from_str_padded("AB").unwrap();
+        assert_eq!(short.buf, *b"AB\0\0");
+        assert_eq!(short, "AB");
+
+        let exact = Ascii::<4>::from_str_padded("ABCD").unwrap();
+        assert_eq!(exact.buf, *b"ABCD");
+
+        let err = Ascii::<4>::from_str_padded("ABCDE").unwrap_err();
+        assert_eq!(err.kind(), AsciiErrorKind::TooLong { capacity: 4 });
+        assert_eq!(err.valid_up_to(), 5);
+        assert_eq!(err.to_string(), "string too long, capacity is 4");
+
+        let err = Ascii::<4>::from_str_padded("A\u{e9}").unwrap_err();
+        assert_eq!(err.kind(), AsciiErrorKind::Invalid);
+        assert_eq!(err.valid_up_to(), 1);
+    }
+
+    #[test]
+    fn map_key() {
+        use std::collections::HashMap;
+
+        let padded = Ascii::<4> { buf: *b"AB\0\0" };
+        let garbage = Ascii::<4> { buf: *b"AB\0Z" };
+        assert_eq!(padded, garbage);
+
+        let mut map = HashMap::new();
+        map.insert(padded, 1);
+        assert_eq!(map.insert(garbage, 2), Some(1));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn ord() {
+        let a = Ascii::<4>::from_str_padded("AB").unwrap();
+        let b = Ascii::<4> { buf: *b"ABC\0" };
+        let c = Ascii::<4> { buf: *b"B\0AA" };
+        assert!(a < b && b < c);
+        assert_eq!(a.cmp(&Ascii { buf: *b"AB\0Z" }), Ordering::Equal);
+    }
+
+    #[test]
+    fn chars() {
+        let ascii = Ascii::<4> { buf: *b"A\xFFB\0" };
+        assert!(ascii.chars().eq(['A', char::REPLACEMENT_CHARACTER, 'B']));
+    }
+}
@@ -1,2 +1,45 @@
+use std::cell::RefCell;
+use std::sync::Once;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
 /// A test ROM.
 pub static ROM: &[u8] = include_bytes!("../../../../external/gba-tests/ppu/stripes.gba");
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<(Level, String)>>> = const { RefCell::new(None) };
+}
+
+/// A logger recording messages logged on threads running [`capture_logs`].
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        CAPTURED.with(|captured| {
+            if let Some(logs) = captured.borrow_mut().as_mut() {
+                logs.push((record.level(), record.args().to_string()));
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs `f`, returning the messages logged on the current thread while it
+/// runs.
+pub fn capture_logs<R>(f: impl FnOnce() -> R) -> (R, Vec<(Level, String)>) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CaptureLogger).expect("logger already set");
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    let result = f();
+    let logs = CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+    (result, logs)
+}